@@ -0,0 +1,26 @@
+use serde::Deserialize;
+use strum_macros::{Display, EnumString};
+
+use crate::process_backend::{NativeBackend, ProcessBackend, SystemdBackend, TmuxBackend};
+
+/// How servers are run in the background
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Display, EnumString, Deserialize)]
+#[strum(serialize_all = "lowercase")]
+#[serde(rename_all = "lowercase")]
+pub enum Backend {
+    #[default]
+    Tmux,
+    Native,
+    Systemd,
+}
+
+impl Backend {
+    /// The implementation that runs servers in this kind of session
+    pub fn sessions(self) -> &'static dyn ProcessBackend {
+        match self {
+            Backend::Tmux => &TmuxBackend,
+            Backend::Native => &NativeBackend,
+            Backend::Systemd => &SystemdBackend,
+        }
+    }
+}
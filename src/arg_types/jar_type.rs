@@ -1,16 +1,26 @@
-use bytes::Bytes;
 use color_eyre::Result;
-use serde::Deserialize;
-use strum_macros::{Display, EnumString};
+use serde::{Deserialize, Serialize};
+use strum_macros::{Display, EnumIter, EnumString};
+use tracing::{info, warn};
 
-use crate::server_jar::{PaperJarProvider, PufferfishJarProvider, ServerJarProvider};
+use crate::server_jar::{
+    self, BuildInfo, CustomJarProvider, FabricJarProvider, JarBuild, JarCache, JarOptions,
+    PaperJarProvider, PufferfishJarProvider, PurpurJarProvider, ServerJar, ServerJarProvider,
+    VanillaJarProvider,
+};
 
-#[derive(Debug, Clone, Copy, PartialEq, Eq, Display, EnumString, Deserialize)]
+#[derive(
+    Debug, Clone, Copy, PartialEq, Eq, Display, EnumString, EnumIter, Serialize, Deserialize,
+)]
 #[strum(serialize_all = "lowercase")]
 #[serde(rename_all = "lowercase")]
 pub enum JarType {
     Paper,
     Pufferfish,
+    Purpur,
+    Vanilla,
+    Fabric,
+    Custom,
 }
 
 impl Default for JarType {
@@ -24,10 +34,134 @@ impl JarType {
         format!("{}.jar", self)
     }
 
-    pub fn download(&self, version: &str) -> Result<Bytes> {
+    pub fn resolve_build(
+        &self,
+        version: &str,
+        build: Option<&str>,
+        options: &JarOptions,
+    ) -> Result<JarBuild> {
         match self {
-            JarType::Paper => PaperJarProvider::download_jar(version),
-            JarType::Pufferfish => PufferfishJarProvider::download_jar(version),
+            JarType::Paper => PaperJarProvider::resolve_build(version, build, options),
+            JarType::Pufferfish => PufferfishJarProvider::resolve_build(version, build, options),
+            JarType::Purpur => PurpurJarProvider::resolve_build(version, build, options),
+            JarType::Vanilla => VanillaJarProvider::resolve_build(version, build, options),
+            JarType::Fabric => FabricJarProvider::resolve_build(version, build, options),
+            JarType::Custom => CustomJarProvider::resolve_build(version, build, options),
         }
     }
+
+    pub fn versions(&self) -> Result<Vec<String>> {
+        match self {
+            JarType::Paper => PaperJarProvider::versions(),
+            JarType::Pufferfish => PufferfishJarProvider::versions(),
+            JarType::Purpur => PurpurJarProvider::versions(),
+            JarType::Vanilla => VanillaJarProvider::versions(),
+            JarType::Fabric => FabricJarProvider::versions(),
+            JarType::Custom => CustomJarProvider::versions(),
+        }
+    }
+
+    pub fn builds(
+        &self,
+        version: &str,
+        limit: usize,
+        options: &JarOptions,
+    ) -> Result<Vec<BuildInfo>> {
+        match self {
+            JarType::Paper => PaperJarProvider::builds(version, limit, options),
+            JarType::Pufferfish => PufferfishJarProvider::builds(version, limit, options),
+            JarType::Purpur => PurpurJarProvider::builds(version, limit, options),
+            JarType::Vanilla => VanillaJarProvider::builds(version, limit, options),
+            JarType::Fabric => FabricJarProvider::builds(version, limit, options),
+            JarType::Custom => CustomJarProvider::builds(version, limit, options),
+        }
+    }
+
+    /// Whether a build id could have been resolved with these options, so falling back to the
+    /// cache never installs a different Fabric loader or installer than the one asked for
+    fn build_matches_options(&self, build: &str, options: &JarOptions) -> bool {
+        match self {
+            // Fabric build ids are `<loader>-<installer>`
+            JarType::Fabric => match build.split_once('-') {
+                Some((loader, installer)) => {
+                    options
+                        .fabric_loader_version
+                        .as_deref()
+                        .is_none_or(|requested| requested == loader)
+                        && options
+                            .fabric_installer_version
+                            .as_deref()
+                            .is_none_or(|requested| requested == installer)
+                }
+                None => false,
+            },
+            _ => true,
+        }
+    }
+
+    /// Downloads the given build, or the latest build if `build` is `None`
+    pub fn download(
+        &self,
+        version: &str,
+        build: Option<&str>,
+        options: &JarOptions,
+        cache: &JarCache,
+    ) -> Result<ServerJar> {
+        // Custom jars can change without their build label changing, so are never cached
+        if *self == JarType::Custom {
+            let build = self.resolve_build(version, build, options)?;
+            return server_jar::download_build(*self, version, &build);
+        }
+
+        // Pinned builds never change, so don't bother asking the build API
+        if let Some(build) = build {
+            if let Some(jar) = cache.get(*self, version, build)? {
+                info!(
+                    "using cached {} build {} for {}",
+                    self.file_name(),
+                    jar.build,
+                    version
+                );
+
+                return Ok(jar);
+            }
+        }
+
+        let build = match self.resolve_build(version, build, options) {
+            Ok(build) => build,
+            Err(error) if build.is_some() => return Err(error),
+            Err(error) => match cache.latest(*self, version, |build| {
+                self.build_matches_options(build, options)
+            })? {
+                None => return Err(error),
+                Some(jar) => {
+                    warn!("failed to query build API: {}", error);
+                    warn!(
+                        "using cached {} build {} for {}",
+                        self.file_name(),
+                        jar.build,
+                        version
+                    );
+
+                    return Ok(jar);
+                }
+            },
+        };
+
+        if let Some(jar) = cache.get(*self, version, &build.build)? {
+            info!(
+                "using cached {} build {} for {}",
+                self.file_name(),
+                jar.build,
+                version
+            );
+
+            return Ok(jar);
+        }
+
+        let jar = server_jar::download_build(*self, version, &build)?;
+        cache.insert(*self, version, &jar)?;
+
+        Ok(jar)
+    }
 }
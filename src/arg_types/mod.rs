@@ -1,7 +1,13 @@
+mod backend;
 mod jar_type;
+mod ready_check;
 mod server_memory;
 mod server_property;
+mod server_selector;
 
+pub use backend::*;
 pub use jar_type::*;
+pub use ready_check::*;
 pub use server_memory::*;
 pub use server_property::*;
+pub use server_selector::*;
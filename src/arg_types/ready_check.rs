@@ -0,0 +1,16 @@
+use serde::Deserialize;
+use strum_macros::{Display, EnumString};
+
+/// How to tell that a server has finished starting
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Display, EnumString, Deserialize)]
+#[strum(serialize_all = "lowercase")]
+#[serde(rename_all = "lowercase")]
+pub enum ReadyCheck {
+    /// The server port accepts connections
+    Port,
+    /// The server answers a server list ping
+    #[default]
+    Ping,
+    /// A line matching a pattern appears in `logs/latest.log`
+    Log,
+}
@@ -8,6 +8,13 @@ use thiserror::Error;
 #[derive(Debug, Clone)]
 pub struct ServerMemory(String, u64);
 
+impl ServerMemory {
+    /// Memory limit for the whole JVM, leaving headroom above the heap for the JVM's own use
+    pub fn limit_bytes(&self) -> u64 {
+        self.1 + (self.1 / 4).max(512 * 1024 * 1024)
+    }
+}
+
 // region: Traits
 impl Display for ServerMemory {
     fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
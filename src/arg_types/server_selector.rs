@@ -0,0 +1,47 @@
+use std::convert::Infallible;
+use std::fmt::Display;
+use std::path::Path;
+use std::str::FromStr;
+
+/// Picks out a server by its index (starting at 1), port, or directory name
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum ServerSelector {
+    Index(u8),
+    Port(u16),
+    Directory(String),
+}
+
+impl FromStr for ServerSelector {
+    type Err = Infallible;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        // Server counts are capped at 255, and ports that low would need root
+        let selector = match s.parse::<u16>() {
+            Ok(index) if index <= u16::from(u8::MAX) => Self::Index(index as u8),
+            Ok(port) => Self::Port(port),
+            Err(_) => Self::Directory(s.trim_end_matches('/').to_owned()),
+        };
+
+        Ok(selector)
+    }
+}
+
+impl ServerSelector {
+    pub fn matches(&self, idx: u8, port: u16, directory: &Path) -> bool {
+        match self {
+            Self::Index(index) => *index == idx,
+            Self::Port(selected) => *selected == port,
+            Self::Directory(name) => Path::new(name) == directory,
+        }
+    }
+}
+
+impl Display for ServerSelector {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            Self::Index(index) => write!(f, "server {}", index),
+            Self::Port(port) => write!(f, "port {}", port),
+            Self::Directory(name) => write!(f, "\"{}\"", name),
+        }
+    }
+}
@@ -0,0 +1,60 @@
+use color_eyre::Result;
+use tracing::info;
+
+use crate::config::GlobalArgs;
+use crate::server_jar::JarCache;
+
+pub fn list(global_args: GlobalArgs) -> Result<()> {
+    let cache = JarCache::new(&global_args.cache_dir);
+    let entries = cache.entries()?;
+
+    if entries.is_empty() {
+        info!("no cached server .jar files in {:?}", cache.root());
+        return Ok(());
+    }
+
+    println!(
+        "{:<12} {:<12} {:<8} {:>10}  SHA256",
+        "TYPE", "VERSION", "BUILD", "SIZE"
+    );
+
+    for entry in entries {
+        let size = match entry.size {
+            Some(size) => format_size(size),
+            None => "missing".into(),
+        };
+
+        println!(
+            "{:<12} {:<12} {:<8} {:>10}  {}",
+            entry.jar_type, entry.version, entry.build, size, entry.sha256
+        );
+    }
+
+    Ok(())
+}
+
+pub fn prune(global_args: GlobalArgs, keep: usize) -> Result<()> {
+    let cache = JarCache::new(&global_args.cache_dir);
+    for entry in cache.prune(keep)? {
+        info!(
+            "removed cached {} build {} for {}",
+            entry.jar_type, entry.build, entry.version
+        );
+    }
+
+    Ok(())
+}
+
+pub fn clear(global_args: GlobalArgs) -> Result<()> {
+    let cache = JarCache::new(&global_args.cache_dir);
+
+    info!("clearing server .jar cache: {:?}", cache.root());
+    cache.clear()?;
+
+    Ok(())
+}
+
+fn format_size(bytes: u64) -> String {
+    let mib = bytes as f64 / (1024.0 * 1024.0);
+    format!("{:.1} MiB", mib)
+}
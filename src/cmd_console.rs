@@ -0,0 +1,160 @@
+use std::io::{self, BufRead};
+use std::path::{Path, PathBuf};
+use std::thread;
+use std::time::Duration;
+
+use color_eyre::Result;
+use tracing::{error, info, warn};
+
+use crate::arg_types::{Backend, ServerSelector};
+use crate::config::GlobalArgs;
+use crate::process_backend::{NativeBackend, TmuxBackend};
+use crate::rcon::{RconClient, RconSettings};
+use crate::server_log::{self, LogPosition};
+use crate::utils;
+
+/// Lines of `logs/latest.log` to show before the console starts
+const CONSOLE_HISTORY: usize = 20;
+
+const POLL_INTERVAL: Duration = Duration::from_millis(250);
+
+/// Where console commands typed into the REPL are sent
+enum ConsoleInput {
+    /// The native backend's console input pipe
+    Stdin,
+    Rcon(RconSettings, RconClient),
+}
+
+/// Attaches to a server's console
+///
+/// tmux sessions are attached to directly. Other backends have no terminal to attach to, so
+/// the server's log is followed while each line typed is sent to its console
+pub fn console(global_args: GlobalArgs, server: &ServerSelector) -> Result<()> {
+    let server_iter = utils::server_iter(
+        global_args.server_count,
+        global_args.start_port,
+        &global_args.directory_template,
+    );
+
+    let directory = match utils::select_servers(server_iter, std::slice::from_ref(server)).next() {
+        Some((_, _, directory, _)) => directory,
+        None => {
+            error!("no server matches {}", server);
+            std::process::exit(1);
+        }
+    };
+
+    let name = directory.to_str().unwrap();
+    if !global_args.backend.sessions().session_exists(name) {
+        error!("\"{}\" is not running", name);
+        std::process::exit(1);
+    }
+
+    let timeout = Duration::from_secs(u64::from(global_args.timeout_secs));
+    let input = match global_args.backend {
+        Backend::Tmux => {
+            if !TmuxBackend::attach(name) {
+                error!("failed to attach to \"{}\"", name);
+                std::process::exit(1);
+            }
+
+            return Ok(());
+        }
+
+        Backend::Native => ConsoleInput::Stdin,
+        Backend::Systemd => match RconSettings::read(&directory)? {
+            Some(settings) => {
+                let client = RconClient::connect(&settings, timeout)?;
+                ConsoleInput::Rcon(settings, client)
+            }
+
+            None => {
+                error!(
+                    "rcon is not enabled for \"{}\", which systemd servers need for console commands",
+                    name
+                );
+                std::process::exit(1);
+            }
+        },
+    };
+
+    repl(&directory, input, timeout)
+}
+
+fn repl(directory: &Path, mut input: ConsoleInput, timeout: Duration) -> Result<()> {
+    let name = directory.to_str().unwrap();
+    for line in server_log::tail(directory, CONSOLE_HISTORY)? {
+        println!("{}", line);
+    }
+
+    let since = LogPosition::end_of(directory);
+    let log_directory = directory.to_path_buf();
+    thread::spawn(move || follow_log(log_directory, since));
+
+    info!(
+        "attached to \"{}\", type commands without a leading slash, press Ctrl-D to detach",
+        name
+    );
+
+    for line in io::stdin().lock().lines() {
+        let line = line?;
+        let command = line.trim().trim_start_matches('/');
+        if command.is_empty() {
+            continue;
+        }
+
+        match &mut input {
+            ConsoleInput::Stdin => {
+                if let Err(error) = NativeBackend::send_console(name, command) {
+                    error!("failed to send command to \"{}\": {}", name, error);
+                }
+            }
+
+            ConsoleInput::Rcon(settings, client) => {
+                // The server may have restarted since connecting, so reconnect once on failure
+                let reply = match client.command(command) {
+                    Ok(reply) => Ok(reply),
+                    Err(_) => RconClient::connect(settings, timeout).and_then(|mut reconnected| {
+                        let reply = reconnected.command(command);
+                        *client = reconnected;
+                        reply
+                    }),
+                };
+
+                match reply {
+                    Ok(reply) => {
+                        for line in reply.lines() {
+                            println!("{}", line);
+                        }
+                    }
+
+                    Err(error) => error!("failed to send command to \"{}\": {}", name, error),
+                }
+            }
+        }
+    }
+
+    Ok(())
+}
+
+/// Prints lines as the server logs them, carrying on into new logs after a restart
+fn follow_log(directory: PathBuf, mut since: Option<LogPosition>) {
+    loop {
+        match server_log::read_since(&directory, since) {
+            Ok((lines, position)) => {
+                for line in lines {
+                    println!("{}", line);
+                }
+
+                since = position;
+            }
+
+            Err(error) => {
+                warn!("stopped following the log: {}", error);
+                return;
+            }
+        }
+
+        thread::sleep(POLL_INTERVAL);
+    }
+}
@@ -0,0 +1,78 @@
+use std::time::Duration;
+
+use color_eyre::Result;
+use tracing::{error, warn};
+
+use crate::arg_types::ServerSelector;
+use crate::config::GlobalArgs;
+use crate::rcon::{RconClient, RconSettings};
+use crate::utils;
+
+/// Sends a console command to each selected server over RCON, printing each reply
+pub fn exec(global_args: GlobalArgs, servers: &[ServerSelector], command: &str) -> Result<()> {
+    let server_iter = utils::server_iter(
+        global_args.server_count,
+        global_args.start_port,
+        &global_args.directory_template,
+    );
+
+    let selected = utils::select_servers(server_iter, servers).collect::<Vec<_>>();
+    if selected.is_empty() {
+        let selectors = servers
+            .iter()
+            .map(ToString::to_string)
+            .collect::<Vec<_>>()
+            .join(", ");
+
+        error!("no server matches {}", selectors);
+        std::process::exit(1);
+    }
+
+    let mut failed = 0;
+    for (_, _, directory, _) in selected {
+        let name = directory.to_str().unwrap();
+        if !directory.exists() {
+            warn!("server \"{}\" does not exist, skipping", name);
+            continue;
+        }
+
+        let settings = match RconSettings::read(&directory) {
+            Ok(Some(settings)) => settings,
+            Ok(None) => {
+                error!("rcon is not enabled for \"{}\"", name);
+                failed += 1;
+                continue;
+            }
+
+            Err(error) => {
+                error!("failed to read rcon settings for \"{}\": {}", name, error);
+                failed += 1;
+                continue;
+            }
+        };
+
+        let timeout = Duration::from_secs(u64::from(global_args.timeout_secs));
+        let result =
+            RconClient::connect(&settings, timeout).and_then(|mut client| client.command(command));
+
+        match result {
+            Ok(reply) if reply.is_empty() => println!("[{}]", name),
+            Ok(reply) => {
+                for line in reply.lines() {
+                    println!("[{}] {}", name, line);
+                }
+            }
+
+            Err(error) => {
+                error!("failed to run command on \"{}\": {}", name, error);
+                failed += 1;
+            }
+        }
+    }
+
+    if failed > 0 {
+        std::process::exit(1);
+    }
+
+    Ok(())
+}
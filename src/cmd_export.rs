@@ -0,0 +1,836 @@
+use std::collections::{BTreeMap, HashSet};
+use std::fs;
+use std::path::{Path, PathBuf};
+
+use color_eyre::Result;
+use fs_extra::dir;
+use serde::Serialize;
+use tracing::{info, warn};
+
+use crate::cmd_init;
+use crate::cmd_start_stop;
+use crate::config::{ExportArgs, GlobalArgs, InitArgs, StartArgs};
+use crate::rcon::RconSettings;
+use crate::server_jar::{self, Checksum};
+use crate::utils;
+
+/// Where each server's directory is mounted inside its container
+const CONTAINER_DIR: &str = "/server";
+
+/// Where config files are mounted for the setup container to copy into the server directory
+const CONFIG_DIR: &str = "/config";
+
+/// Image for the container that prepares each server's volume before it starts
+const SETUP_IMAGE: &str = "busybox:1.36";
+
+/// Copies config files into the volume, adds the RCON password from its Secret, then downloads
+/// the server .jar if the build has changed
+///
+/// `cp -L` follows the symlinks in ConfigMap volumes, and `*` skips their hidden `..data` entries
+const SETUP_SCRIPT: &str = r#"set -e
+cp -rL /config/* /server/
+cd /server
+if [ -n "$RCON_PASSWORD" ]; then echo "rcon.password=$RCON_PASSWORD" >> server.properties; fi
+if [ -z "$JAR_URL" ] || [ "$(cat .jar_url 2>/dev/null)" = "$JAR_URL" ]; then exit 0; fi
+wget -q -O "$JAR_FILE.tmp" "$JAR_URL"
+if [ -n "$JAR_CHECKSUM" ]; then echo "$JAR_CHECKSUM  $JAR_FILE.tmp" | "$CHECKSUM_TOOL" -c -; fi
+mv "$JAR_FILE.tmp" "$JAR_FILE"
+echo "$JAR_URL" > .jar_url
+"#;
+
+/// Largest ConfigMap Kubernetes will store
+const CONFIG_MAP_LIMIT: usize = 1024 * 1024;
+
+/// Splits the server command into arguments, as containers don't run it through a shell
+fn java_command(start_args: StartArgs, jar_file: &str) -> Vec<String> {
+    let jvm_args = cmd_start_stop::generate_jvm_args(start_args);
+
+    let mut command = vec!["java".to_owned()];
+    command.extend(jvm_args.split_whitespace().map(str::to_owned));
+    command.extend(["-jar".to_owned(), jar_file.to_owned(), "nogui".to_owned()]);
+
+    command
+}
+
+/// Writes the generated file with a header comment, creating its directory if needed
+fn write_output(output: &Path, header: &str, body: &str) -> Result<()> {
+    if let Some(parent) = output.parent() {
+        fs::create_dir_all(parent)?;
+    }
+
+    let contents = format!("# Generated by provisioner\n{}{}", header, body);
+    fs::write(output, contents)?;
+
+    info!("wrote {:?}", output);
+    Ok(())
+}
+
+// region: Docker Compose
+#[derive(Debug, Serialize)]
+struct ComposeFile {
+    /// Keeps services in the order they were added, servers first
+    services: serde_yaml::Mapping,
+}
+
+#[derive(Debug, Serialize)]
+struct ServerService {
+    image: String,
+    working_dir: &'static str,
+    command: Vec<String>,
+    volumes: Vec<String>,
+    ports: Vec<String>,
+    mem_limit: String,
+    stop_grace_period: String,
+    restart: &'static str,
+    /// Keeps a console open for `docker attach`
+    stdin_open: bool,
+    tty: bool,
+}
+
+#[derive(Debug, Serialize)]
+struct ProxyService {
+    image: String,
+    ports: Vec<String>,
+    depends_on: Vec<String>,
+    restart: &'static str,
+}
+
+/// Published ports of a server
+///
+/// RCON is only published on the host's loopback interface, as it gives full console access
+fn published_ports(port: u16, rcon_port: Option<u16>) -> Vec<String> {
+    let mut ports = vec![format!("{0}:{0}", port)];
+    if let Some(rcon_port) = rcon_port {
+        ports.push(format!("127.0.0.1:{0}:{0}", rcon_port));
+    }
+
+    ports
+}
+
+/// Writes a Docker Compose file with a service for each server, bind mounting its directory
+pub fn compose(
+    global_args: GlobalArgs,
+    start_args: StartArgs,
+    export_args: ExportArgs,
+    output: &Path,
+) -> Result<()> {
+    let memory_limit = start_args.max_memory.limit_bytes() / (1024 * 1024);
+    let command = java_command(start_args, &global_args.jar_type.file_name());
+
+    // Mount paths are relative to the compose file, so only keep them relative beside it
+    let relative = output
+        .parent()
+        .is_none_or(|parent| parent.as_os_str().is_empty() || parent == Path::new("."));
+    let current_dir = std::env::current_dir()?;
+
+    let server_iter = utils::server_iter(
+        global_args.server_count,
+        global_args.start_port,
+        &global_args.directory_template,
+    );
+
+    let mut services = serde_yaml::Mapping::new();
+    let mut names = vec![];
+    let mut addresses = vec![];
+    for (_, port, directory, _) in server_iter {
+        let name = directory.to_str().unwrap().to_owned();
+        if !directory.exists() {
+            warn!(
+                "server \"{}\" has not been initialised, run init first",
+                name
+            );
+        }
+
+        let host_dir = match relative {
+            true => format!("./{}", name),
+            false => current_dir.join(&directory).display().to_string(),
+        };
+
+        let rcon_port = match RconSettings::read(&directory) {
+            Ok(Some(rcon)) => Some(rcon.port),
+            _ => None,
+        };
+
+        let service = ServerService {
+            image: export_args.image.clone(),
+            working_dir: CONTAINER_DIR,
+            command: command.clone(),
+            volumes: vec![format!("{}:{}", host_dir, CONTAINER_DIR)],
+            ports: published_ports(port, rcon_port),
+            mem_limit: format!("{}m", memory_limit),
+            stop_grace_period: format!("{}s", global_args.timeout_secs),
+            restart: "unless-stopped",
+            stdin_open: true,
+            tty: true,
+        };
+
+        services.insert(name.clone().into(), serde_yaml::to_value(service)?);
+        addresses.push(format!("{}:{}", name, port));
+        names.push(name);
+    }
+
+    if let Some(proxy) = &export_args.proxy {
+        let proxy_service = ProxyService {
+            image: proxy.image.clone(),
+            ports: vec![format!("{}:25577", proxy.port)],
+            depends_on: names,
+            restart: "unless-stopped",
+        };
+
+        services.insert("proxy".into(), serde_yaml::to_value(proxy_service)?);
+    }
+
+    let header = match export_args.proxy {
+        Some(_) => format!(
+            "# The proxy can reach the servers at {}\n",
+            addresses.join(", ")
+        ),
+        None => String::new(),
+    };
+
+    let document = serde_yaml::to_string(&ComposeFile { services })?;
+    write_output(output, &header, &document)
+}
+// endregion
+
+// region: Kubernetes
+type Labels = BTreeMap<&'static str, String>;
+
+/// Memory or storage amounts, keyed by resource name
+type ResourceList = BTreeMap<&'static str, String>;
+
+/// A Kubernetes object, with the fields specific to its kind flattened in after the metadata
+#[derive(Debug, Serialize)]
+#[serde(rename_all = "camelCase")]
+struct Resource<T> {
+    api_version: &'static str,
+    kind: &'static str,
+    metadata: ObjectMeta,
+    #[serde(flatten)]
+    body: T,
+}
+
+#[derive(Debug, Clone, Serialize)]
+struct ObjectMeta {
+    name: String,
+    labels: Labels,
+}
+
+#[derive(Debug, Serialize)]
+struct ConfigMapBody {
+    data: BTreeMap<String, String>,
+}
+
+#[derive(Debug, Serialize)]
+#[serde(rename_all = "camelCase")]
+struct SecretBody {
+    #[serde(rename = "type")]
+    kind: &'static str,
+    string_data: BTreeMap<&'static str, String>,
+}
+
+#[derive(Debug, Serialize)]
+struct SpecBody<T> {
+    spec: T,
+}
+
+#[derive(Debug, Serialize)]
+#[serde(rename_all = "camelCase")]
+struct VolumeClaimSpec {
+    access_modes: Vec<&'static str>,
+    resources: VolumeClaimResources,
+}
+
+#[derive(Debug, Serialize)]
+struct VolumeClaimResources {
+    requests: ResourceList,
+}
+
+#[derive(Debug, Serialize)]
+#[serde(rename_all = "camelCase")]
+struct DeploymentSpec {
+    replicas: u16,
+    strategy: DeploymentStrategy,
+    selector: LabelSelector,
+    template: PodTemplate,
+}
+
+#[derive(Debug, Serialize)]
+struct DeploymentStrategy {
+    #[serde(rename = "type")]
+    kind: &'static str,
+}
+
+#[derive(Debug, Serialize)]
+#[serde(rename_all = "camelCase")]
+struct LabelSelector {
+    match_labels: Labels,
+}
+
+#[derive(Debug, Serialize)]
+struct PodTemplate {
+    metadata: PodMeta,
+    spec: PodSpec,
+}
+
+#[derive(Debug, Serialize)]
+struct PodMeta {
+    labels: Labels,
+}
+
+#[derive(Debug, Serialize)]
+#[serde(rename_all = "camelCase")]
+struct PodSpec {
+    termination_grace_period_seconds: u32,
+    init_containers: Vec<Container>,
+    containers: Vec<Container>,
+    volumes: Vec<Volume>,
+}
+
+#[derive(Debug, Serialize)]
+#[serde(rename_all = "camelCase")]
+struct Container {
+    name: &'static str,
+    image: String,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    working_dir: Option<&'static str>,
+    command: Vec<String>,
+    #[serde(skip_serializing_if = "Vec::is_empty")]
+    env: Vec<EnvVar>,
+    #[serde(skip_serializing_if = "Vec::is_empty")]
+    ports: Vec<ContainerPort>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    resources: Option<ContainerResources>,
+    volume_mounts: Vec<VolumeMount>,
+    /// Keeps a console open for `kubectl attach`
+    #[serde(skip_serializing_if = "Option::is_none")]
+    stdin: Option<bool>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    tty: Option<bool>,
+}
+
+#[derive(Debug, Clone, Serialize)]
+#[serde(rename_all = "camelCase")]
+struct EnvVar {
+    name: &'static str,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    value: Option<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    value_from: Option<EnvVarSource>,
+}
+
+#[derive(Debug, Clone, Serialize)]
+#[serde(rename_all = "camelCase")]
+struct EnvVarSource {
+    secret_key_ref: SecretKeySelector,
+}
+
+#[derive(Debug, Clone, Serialize)]
+struct SecretKeySelector {
+    name: String,
+    key: &'static str,
+}
+
+#[derive(Debug, Serialize)]
+#[serde(rename_all = "camelCase")]
+struct ContainerPort {
+    name: &'static str,
+    container_port: u16,
+}
+
+#[derive(Debug, Serialize)]
+struct ContainerResources {
+    requests: ResourceList,
+    limits: ResourceList,
+}
+
+#[derive(Debug, Clone, Serialize)]
+#[serde(rename_all = "camelCase")]
+struct VolumeMount {
+    name: &'static str,
+    mount_path: &'static str,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    read_only: Option<bool>,
+}
+
+#[derive(Debug, Serialize)]
+#[serde(rename_all = "camelCase")]
+struct Volume {
+    name: &'static str,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    persistent_volume_claim: Option<ClaimSource>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    projected: Option<ProjectedSource>,
+}
+
+#[derive(Debug, Serialize)]
+#[serde(rename_all = "camelCase")]
+struct ClaimSource {
+    claim_name: String,
+}
+
+#[derive(Debug, Serialize)]
+struct ProjectedSource {
+    sources: Vec<ProjectionSource>,
+}
+
+#[derive(Debug, Serialize)]
+#[serde(rename_all = "camelCase")]
+struct ProjectionSource {
+    config_map: ConfigMapProjection,
+}
+
+#[derive(Debug, Serialize)]
+struct ConfigMapProjection {
+    name: String,
+    #[serde(skip_serializing_if = "Vec::is_empty")]
+    items: Vec<KeyToPath>,
+}
+
+#[derive(Debug, Clone, Serialize)]
+struct KeyToPath {
+    key: String,
+    path: String,
+}
+
+#[derive(Debug, Serialize)]
+struct ServiceSpec {
+    selector: Labels,
+    ports: Vec<ServicePort>,
+}
+
+#[derive(Debug, Serialize)]
+#[serde(rename_all = "camelCase")]
+struct ServicePort {
+    name: &'static str,
+    port: u16,
+    target_port: &'static str,
+}
+
+/// Writes Kubernetes manifests with a Deployment, volume claim, ConfigMap and Service per server
+///
+/// Config files are generated from the `[init]` settings rather than read from each server
+/// directory, and are copied into the server's volume along with the synced directories
+/// whenever its pod starts. The RCON password is kept in a Secret, which is only generated
+/// when `rcon_password` is set and otherwise has to exist already
+pub fn kubernetes(
+    global_args: GlobalArgs,
+    init_args: InitArgs,
+    start_args: StartArgs,
+    export_args: ExportArgs,
+    output: &Path,
+) -> Result<()> {
+    let cluster = resource_name(&global_args.directory_template);
+    let memory = format!("{}Mi", start_args.max_memory.limit_bytes() / (1024 * 1024));
+    let command = java_command(start_args, &global_args.jar_type.file_name());
+    let mut setup_env = setup_env(&global_args)?;
+    let mut documents = vec![];
+
+    // The local servers aren't read, so the password has to come from the config or the cluster
+    let rcon_secret = format!("{}-rcon", cluster);
+    let rcon_start_port = init_args
+        .rcon
+        .as_ref()
+        .map(|rcon| cmd_init::rcon_start_port(&global_args, rcon));
+
+    if let Some(rcon) = &init_args.rcon {
+        match &rcon.password {
+            Some(password) => documents.push(serde_yaml::to_string(&Resource {
+                api_version: "v1",
+                kind: "Secret",
+                metadata: ObjectMeta {
+                    name: rcon_secret.clone(),
+                    labels: cluster_labels(&cluster),
+                },
+                body: SecretBody {
+                    kind: "Opaque",
+                    string_data: BTreeMap::from([("password", password.clone())]),
+                },
+            })?),
+            None => warn!(
+                "rcon_password is not set, create the \"{}\" Secret with a password key before applying",
+                rcon_secret
+            ),
+        }
+
+        setup_env.push(EnvVar {
+            name: "RCON_PASSWORD",
+            value: None,
+            value_from: Some(EnvVarSource {
+                secret_key_ref: SecretKeySelector {
+                    name: rcon_secret,
+                    key: "password",
+                },
+            }),
+        });
+    }
+
+    // Synced files are the same for every server, so they share one ConfigMap
+    let synced_files = synced_files(&global_args.sync_dirs)?;
+    let synced_paths = synced_files
+        .iter()
+        .map(|(path, _)| path.as_str())
+        .collect::<HashSet<_>>();
+
+    let sync_config_map = format!("{}-sync", cluster);
+    let mut sync_items = vec![];
+    if !synced_files.is_empty() {
+        let mut data = BTreeMap::new();
+        for (idx, (path, contents)) in synced_files.iter().enumerate() {
+            // Keys only need to be unique, the items put each file at its real path
+            let key = format!("{}-{}", idx, config_key(path));
+            sync_items.push(KeyToPath {
+                key: key.clone(),
+                path: path.clone(),
+            });
+
+            data.insert(key, contents.clone());
+        }
+
+        documents.push(config_map(&sync_config_map, &cluster, data)?);
+    }
+
+    let server_iter = utils::server_iter(
+        global_args.server_count,
+        global_args.start_port,
+        &global_args.directory_template,
+    );
+
+    for (idx, port, directory, motd) in server_iter {
+        let name = resource_name(directory.to_str().unwrap());
+        let labels = Labels::from([
+            ("app.kubernetes.io/name", name.clone()),
+            ("app.kubernetes.io/part-of", cluster.clone()),
+            ("app.kubernetes.io/managed-by", "provisioner".to_owned()),
+        ]);
+
+        let metadata = |name: &str| ObjectMeta {
+            name: name.to_owned(),
+            labels: labels.clone(),
+        };
+
+        // Synced files overwrite the generated ones, as they do with `init`
+        let rcon = rcon_start_port.map(|start_port| (start_port, None));
+        let server_files = cmd_init::server_files(&global_args, &init_args, rcon, idx, port, &motd);
+
+        let data = server_files
+            .into_iter()
+            .filter(|(file_name, _)| !synced_paths.contains(file_name))
+            .map(|(file_name, contents)| (file_name.to_owned(), contents))
+            .collect();
+
+        let server_config_map = format!("{}-config", name);
+        documents.push(config_map(&server_config_map, &cluster, data)?);
+
+        let claim = format!("{}-data", name);
+        documents.push(serde_yaml::to_string(&Resource {
+            api_version: "v1",
+            kind: "PersistentVolumeClaim",
+            metadata: metadata(&claim),
+            body: SpecBody {
+                spec: VolumeClaimSpec {
+                    access_modes: vec!["ReadWriteOnce"],
+                    resources: VolumeClaimResources {
+                        requests: ResourceList::from([(
+                            "storage",
+                            export_args.storage_size.clone(),
+                        )]),
+                    },
+                },
+            },
+        })?);
+
+        let mut ports = vec![("minecraft", port)];
+        if let Some(rcon_start_port) = rcon_start_port {
+            ports.push(("rcon", rcon_start_port + (u16::from(idx) - 1)));
+        }
+
+        let mut config_sources = vec![ProjectionSource {
+            config_map: ConfigMapProjection {
+                name: server_config_map,
+                items: vec![],
+            },
+        }];
+
+        if !sync_items.is_empty() {
+            config_sources.push(ProjectionSource {
+                config_map: ConfigMapProjection {
+                    name: sync_config_map.clone(),
+                    items: sync_items.clone(),
+                },
+            });
+        }
+
+        let data_mount = VolumeMount {
+            name: "data",
+            mount_path: CONTAINER_DIR,
+            read_only: None,
+        };
+
+        let config_mount = VolumeMount {
+            name: "config",
+            mount_path: CONFIG_DIR,
+            read_only: Some(true),
+        };
+
+        let setup_container = Container {
+            name: "setup",
+            image: SETUP_IMAGE.to_owned(),
+            working_dir: None,
+            command: vec!["sh".to_owned(), "-c".to_owned(), SETUP_SCRIPT.to_owned()],
+            env: setup_env.clone(),
+            ports: vec![],
+            resources: None,
+            volume_mounts: vec![data_mount.clone(), config_mount],
+            stdin: None,
+            tty: None,
+        };
+
+        let container_ports = ports
+            .iter()
+            .map(|(port_name, port)| ContainerPort {
+                name: port_name,
+                container_port: *port,
+            })
+            .collect();
+
+        let memory_list = ResourceList::from([("memory", memory.clone())]);
+        let server_container = Container {
+            name: "server",
+            image: export_args.image.clone(),
+            working_dir: Some(CONTAINER_DIR),
+            command: command.clone(),
+            env: vec![],
+            ports: container_ports,
+            resources: Some(ContainerResources {
+                requests: memory_list.clone(),
+                limits: memory_list,
+            }),
+            volume_mounts: vec![data_mount],
+            stdin: Some(true),
+            tty: Some(true),
+        };
+
+        let volumes = vec![
+            Volume {
+                name: "data",
+                persistent_volume_claim: Some(ClaimSource { claim_name: claim }),
+                projected: None,
+            },
+            Volume {
+                name: "config",
+                persistent_volume_claim: None,
+                projected: Some(ProjectedSource {
+                    sources: config_sources,
+                }),
+            },
+        ];
+
+        documents.push(serde_yaml::to_string(&Resource {
+            api_version: "apps/v1",
+            kind: "Deployment",
+            metadata: metadata(&name),
+            body: SpecBody {
+                spec: DeploymentSpec {
+                    replicas: 1,
+                    // The volume can only be mounted by one pod at a time
+                    strategy: DeploymentStrategy { kind: "Recreate" },
+                    selector: LabelSelector {
+                        match_labels: labels.clone(),
+                    },
+                    template: PodTemplate {
+                        metadata: PodMeta {
+                            labels: labels.clone(),
+                        },
+                        spec: PodSpec {
+                            termination_grace_period_seconds: u32::from(global_args.timeout_secs),
+                            init_containers: vec![setup_container],
+                            containers: vec![server_container],
+                            volumes,
+                        },
+                    },
+                },
+            },
+        })?);
+
+        let service_ports = ports
+            .iter()
+            .map(|(port_name, port)| ServicePort {
+                name: port_name,
+                port: *port,
+                target_port: port_name,
+            })
+            .collect();
+
+        documents.push(serde_yaml::to_string(&Resource {
+            api_version: "v1",
+            kind: "Service",
+            metadata: metadata(&name),
+            body: SpecBody {
+                spec: ServiceSpec {
+                    selector: labels.clone(),
+                    ports: service_ports,
+                },
+            },
+        })?);
+    }
+
+    write_output(output, "", &documents.join("---\n"))
+}
+
+/// Labels for resources shared by every server in the cluster
+fn cluster_labels(cluster: &str) -> Labels {
+    Labels::from([
+        ("app.kubernetes.io/part-of", cluster.to_owned()),
+        ("app.kubernetes.io/managed-by", "provisioner".to_owned()),
+    ])
+}
+
+fn config_map(name: &str, cluster: &str, data: BTreeMap<String, String>) -> Result<String> {
+    let size = data.values().map(String::len).sum::<usize>();
+    if size > CONFIG_MAP_LIMIT {
+        warn!(
+            "ConfigMap \"{}\" holds {} bytes, which is over the 1MiB Kubernetes allows",
+            name, size
+        );
+    }
+
+    let document = serde_yaml::to_string(&Resource {
+        api_version: "v1",
+        kind: "ConfigMap",
+        metadata: ObjectMeta {
+            name: name.to_owned(),
+            labels: cluster_labels(cluster),
+        },
+        body: ConfigMapBody { data },
+    })?;
+
+    Ok(document)
+}
+
+/// Tells the setup script which server .jar build to download, and how to verify it
+fn setup_env(global_args: &GlobalArgs) -> Result<Vec<EnvVar>> {
+    let build = global_args.jar_type.resolve_build(
+        &global_args.jar_version,
+        global_args.jar_build.as_deref(),
+        &global_args.jar_options,
+    )?;
+
+    let jar_file = global_args.jar_type.file_name();
+    let jar_url = match server_jar::is_url(&build.download_url) {
+        true => build.download_url.as_str(),
+        false => {
+            warn!(
+                "{:?} is a local file, copy it into each server's volume as {}",
+                build.download_url, jar_file
+            );
+            ""
+        }
+    };
+
+    let (checksum_tool, checksum) = match &build.checksum {
+        Some(Checksum::Sha256(checksum)) => ("sha256sum", checksum.as_str()),
+        Some(Checksum::Sha1(checksum)) => ("sha1sum", checksum.as_str()),
+        Some(Checksum::Md5(checksum)) => ("md5sum", checksum.as_str()),
+        None => ("", ""),
+    };
+
+    let env = [
+        ("JAR_FILE", jar_file.as_str()),
+        ("JAR_URL", jar_url),
+        ("JAR_CHECKSUM", checksum),
+        ("CHECKSUM_TOOL", checksum_tool),
+    ];
+
+    let env = env
+        .into_iter()
+        .map(|(name, value)| EnvVar {
+            name,
+            value: Some(value.to_owned()),
+            value_from: None,
+        })
+        .collect();
+
+    Ok(env)
+}
+
+/// Text files in each sync directory, as `(path in the server directory, contents)`
+fn synced_files(sync_dirs: &[PathBuf]) -> Result<Vec<(String, String)>> {
+    let mut files = vec![];
+    for source_dir in sync_dirs {
+        if !source_dir.is_dir() {
+            warn!("{:?} is not a directory, skipping sync", source_dir);
+            continue;
+        }
+
+        let target_dir = utils::sync_target_dir(source_dir, Path::new(""));
+        for file in dir::get_dir_content(source_dir)?.files {
+            let file = PathBuf::from(file);
+            let path = target_dir.join(file.strip_prefix(source_dir)?);
+
+            match String::from_utf8(fs::read(&file)?) {
+                Ok(contents) => files.push((path.to_str().unwrap().to_owned(), contents)),
+                Err(_) => warn!(
+                    "skipping {:?} as ConfigMaps only hold text, copy it into each server's volume",
+                    file
+                ),
+            }
+        }
+    }
+
+    Ok(files)
+}
+
+/// Lowercase alphanumerics separated by dashes, as Kubernetes requires for most names
+fn resource_name(name: &str) -> String {
+    let mut resource = String::new();
+    for c in name.chars() {
+        match c.is_ascii_alphanumeric() {
+            true => resource.push(c.to_ascii_lowercase()),
+            false if !resource.is_empty() && !resource.ends_with('-') => resource.push('-'),
+            false => (),
+        }
+    }
+
+    resource.trim_end_matches('-').to_owned()
+}
+
+/// ConfigMap keys can't contain path separators
+fn config_key(path: &str) -> String {
+    path.chars()
+        .map(|c| match c.is_ascii_alphanumeric() || "-._".contains(c) {
+            true => c,
+            false => '_',
+        })
+        .collect()
+}
+// endregion
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_published_ports() {
+        assert_eq!(published_ports(25565, None), vec!["25565:25565"]);
+        assert_eq!(
+            published_ports(25565, Some(35565)),
+            vec!["25565:25565", "127.0.0.1:35565:35565"]
+        );
+    }
+
+    #[test]
+    fn test_resource_names() {
+        assert_eq!(
+            resource_name("mammoth_server_25565"),
+            "mammoth-server-25565"
+        );
+        assert_eq!(resource_name("Mammoth Server"), "mammoth-server");
+        assert_eq!(resource_name("__My  Server!"), "my-server");
+
+        assert_eq!(
+            config_key("plugins/Essentials/config.yml"),
+            "plugins_Essentials_config.yml"
+        );
+    }
+}
@@ -1,34 +1,33 @@
+use std::collections::HashSet;
 use std::fs;
 
 use color_eyre::Result;
 use fs_extra::dir::{self, CopyOptions};
-use tracing::{info, warn};
+use rand::distributions::Alphanumeric;
+use rand::{thread_rng, Rng};
+use tracing::{error, info, warn};
 
-use crate::config::{GlobalArgs, InitArgs};
+use crate::config::{GlobalArgs, InitArgs, RconArgs};
+use crate::server_jar::{self, JarCache};
 use crate::utils;
 
 pub fn init(global_args: GlobalArgs, args: InitArgs) -> Result<()> {
-    let server_jar = global_args.jar_type.download(&global_args.jar_version)?;
-
-    let has_ops = !args.ops.is_empty();
-    let ops = args
-        .ops
-        .into_iter()
-        .map(|p| format!("{}\n", p))
-        .collect::<String>();
-
-    let has_white_list = !args.white_list.is_empty();
-    let white_list = args
-        .white_list
-        .into_iter()
-        .map(|p| format!("{}\n", p))
-        .collect::<String>();
+    let cache = JarCache::new(&global_args.cache_dir);
+    let server_jar = global_args.jar_type.download(
+        &global_args.jar_version,
+        global_args.jar_build.as_deref(),
+        &global_args.jar_options,
+        &cache,
+    )?;
+
+    let rcon = match &args.rcon {
+        Some(rcon) => Some(rcon_settings(&global_args, rcon)?),
+        None => None,
+    };
 
-    let extra_props = args
-        .server_properties
-        .into_iter()
-        .map(|p| format!("{}\n", p))
-        .collect::<String>();
+    let rcon = rcon
+        .as_ref()
+        .map(|(start_port, password)| (*start_port, Some(password.as_str())));
 
     let server_iter = utils::server_iter(
         global_args.server_count,
@@ -45,33 +44,23 @@ pub fn init(global_args: GlobalArgs, args: InitArgs) -> Result<()> {
         options
     };
 
-    for (_, port, directory, motd) in server_iter {
+    for (idx, port, directory, motd) in server_iter {
         info!("creating server: {:?}", &directory);
         if !directory.exists() {
             fs::create_dir(&directory)?;
         }
 
-        fs::write(directory.join("eula.txt"), "eula=true\n")?;
-        fs::write(
-            directory.join(global_args.jar_type.file_name()),
+        server_jar::install_jar(
+            &directory,
+            global_args.jar_type,
+            &global_args.jar_version,
             &server_jar,
         )?;
 
-        if has_ops {
-            fs::write(directory.join("ops.txt"), &ops)?;
+        for (file_name, contents) in server_files(&global_args, &args, rcon, idx, port, &motd) {
+            fs::write(directory.join(file_name), contents)?;
         }
 
-        if has_white_list {
-            fs::write(directory.join("whitelist.txt"), &white_list)?;
-        }
-
-        let properties = format!(
-            "level-seed={}\nmotd={}\nquery.port={}\nserver-port={}\nlevel-name={}\n{}",
-            &args.level_seed, motd, port, port, &global_args.level_name, &extra_props
-        );
-
-        fs::write(directory.join("server.properties"), properties)?;
-
         for source_dir in &global_args.sync_dirs {
             if !source_dir.exists() {
                 warn!("directory {:?} does not exist, skipping sync", source_dir);
@@ -83,15 +72,7 @@ pub fn init(global_args: GlobalArgs, args: InitArgs) -> Result<()> {
                 continue;
             }
 
-            let is_plugins_dir = match source_dir.file_name() {
-                Some(dir) => dir == "plugins",
-                _ => false,
-            };
-
-            let target_dir = match is_plugins_dir {
-                true => directory.join("plugins"),
-                false => directory.clone(),
-            };
+            let target_dir = utils::sync_target_dir(source_dir, &directory);
 
             dir::copy(&source_dir, &target_dir, &options)?;
         }
@@ -99,3 +80,121 @@ pub fn init(global_args: GlobalArgs, args: InitArgs) -> Result<()> {
 
     Ok(())
 }
+
+/// Config files written into each server directory, as `(file name, contents)`
+///
+/// `rcon` is the first RCON port and the password, which is left out of `server.properties`
+/// when `None` so that it can be added when the server starts
+pub fn server_files(
+    global_args: &GlobalArgs,
+    args: &InitArgs,
+    rcon: Option<(u16, Option<&str>)>,
+    idx: u8,
+    port: u16,
+    motd: &str,
+) -> Vec<(&'static str, String)> {
+    let mut files = vec![("eula.txt", "eula=true\n".to_owned())];
+
+    if !args.ops.is_empty() {
+        files.push(("ops.txt", sorted_lines(&args.ops)));
+    }
+
+    if !args.white_list.is_empty() {
+        files.push(("whitelist.txt", sorted_lines(&args.white_list)));
+    }
+
+    let extra_props = args
+        .server_properties
+        .iter()
+        .map(|p| format!("{}\n", p))
+        .collect::<String>();
+
+    let mut properties = format!(
+        "level-seed={}\nmotd={}\nquery.port={}\nserver-port={}\nlevel-name={}\n{}",
+        &args.level_seed, motd, port, port, &global_args.level_name, &extra_props
+    );
+
+    // Written last, so these take priority over any extra properties
+    if let Some((rcon_start_port, password)) = rcon {
+        properties.push_str(&format!(
+            "enable-rcon=true\nrcon.port={}\n",
+            rcon_start_port + (u16::from(idx) - 1)
+        ));
+
+        if let Some(password) = password {
+            properties.push_str(&format!("rcon.password={}\n", password));
+        }
+    }
+
+    files.push(("server.properties", properties));
+    files
+}
+
+fn sorted_lines(names: &HashSet<String>) -> String {
+    let mut names = names.iter().collect::<Vec<_>>();
+    names.sort();
+
+    names
+        .into_iter()
+        .map(|name| format!("{}\n", name))
+        .collect()
+}
+
+/// Picks RCON ports that don't overlap the game ports, and a password shared by all servers
+pub fn rcon_settings(global_args: &GlobalArgs, rcon: &RconArgs) -> Result<(u16, String)> {
+    let start_port = rcon_start_port(global_args, rcon);
+
+    // Keep the existing password so running servers can still be reached
+    let existing = utils::server_iter(
+        global_args.server_count,
+        global_args.start_port,
+        &global_args.directory_template,
+    )
+    .filter(|(_, _, directory, _)| directory.exists())
+    .find_map(|(_, _, directory, _)| {
+        let properties = utils::read_server_properties(&directory).ok()?;
+        properties
+            .get("rcon.password")
+            .filter(|p| !p.is_empty())
+            .cloned()
+    });
+
+    let password = match rcon.password.clone().or(existing) {
+        Some(password) => password,
+        None => {
+            info!("generated a new rcon password");
+            thread_rng()
+                .sample_iter(&Alphanumeric)
+                .take(24)
+                .map(char::from)
+                .collect()
+        }
+    };
+
+    Ok((start_port, password))
+}
+
+/// First RCON port, exiting if the RCON ports would overlap the game ports
+pub fn rcon_start_port(global_args: &GlobalArgs, rcon: &RconArgs) -> u16 {
+    let count = u16::from(global_args.server_count);
+    let game_ports = global_args.start_port..global_args.start_port + count;
+
+    let start_port = rcon
+        .start_port
+        .or_else(|| global_args.start_port.checked_add(10000));
+
+    let rcon_ports = start_port.and_then(|start| Some(start..start.checked_add(count)?));
+    let rcon_ports = match rcon_ports {
+        Some(ports) if ports.end <= game_ports.start || ports.start >= game_ports.end => ports,
+        _ => {
+            error!(
+                "rcon ports must not overlap the server ports {} to {}, set --rcon-start-port",
+                game_ports.start,
+                game_ports.end - 1
+            );
+            std::process::exit(1);
+        }
+    };
+
+    rcon_ports.start
+}
@@ -0,0 +1,127 @@
+use std::io::IsTerminal;
+use std::path::PathBuf;
+use std::thread;
+use std::time::Duration;
+
+use color_eyre::Result;
+use regex::Regex;
+use tracing::warn;
+
+use crate::arg_types::ServerSelector;
+use crate::config::GlobalArgs;
+use crate::server_log::{self, LogPosition};
+use crate::utils;
+
+const POLL_INTERVAL: Duration = Duration::from_millis(250);
+
+/// ANSI colours cycled through for server labels
+const LABEL_COLOURS: [u8; 6] = [36, 33, 35, 32, 34, 31];
+
+struct ServerLog {
+    directory: PathBuf,
+    label: String,
+    position: Option<LogPosition>,
+}
+
+/// Prints the last `line_count` matching lines of each selected server's log, interleaved by time
+/// and labelled with the server
+///
+/// With `follow`, keeps printing lines as they're written, following each log across restarts
+pub fn logs(
+    global_args: GlobalArgs,
+    servers: &[ServerSelector],
+    follow: bool,
+    line_count: usize,
+    archived: bool,
+    pattern: Option<Regex>,
+) -> Result<()> {
+    let server_iter = utils::server_iter(
+        global_args.server_count,
+        global_args.start_port,
+        &global_args.directory_template,
+    );
+
+    let directories = utils::select_servers(server_iter, servers)
+        .map(|(_, _, directory, _)| directory)
+        .filter(|directory| match directory.exists() {
+            true => true,
+            false => {
+                warn!("server {:?} does not exist, skipping", directory);
+                false
+            }
+        })
+        .collect::<Vec<_>>();
+
+    let width = directories
+        .iter()
+        .map(|directory| directory.as_os_str().len())
+        .max()
+        .unwrap_or(0);
+
+    let colour = std::io::stdout().is_terminal();
+    let mut logs = vec![];
+    let mut lines = vec![];
+    for (idx, directory) in directories.into_iter().enumerate() {
+        let name = format!("{:width$}", directory.to_str().unwrap(), width = width);
+        let label = match colour {
+            true => format!(
+                "\x1b[{}m{}\x1b[0m",
+                LABEL_COLOURS[idx % LABEL_COLOURS.len()],
+                name
+            ),
+            false => name,
+        };
+
+        let (mut server_lines, position) = server_log::read_all(&directory, archived)?;
+        server_lines.retain(|line| is_match(&line.line, pattern.as_ref()));
+
+        let skip = server_lines.len().saturating_sub(line_count);
+        lines.extend(server_lines.into_iter().skip(skip).map(|line| (line, idx)));
+
+        logs.push(ServerLog {
+            directory,
+            label,
+            position,
+        });
+    }
+
+    // Stable, so lines logged in the same second stay in order
+    lines.sort_by_key(|(line, _)| line.time);
+    for (line, idx) in lines {
+        println!("{} | {}", logs[idx].label, line.line);
+    }
+
+    if !follow {
+        return Ok(());
+    }
+
+    loop {
+        for log in &mut logs {
+            let (lines, position) = match server_log::read_since(&log.directory, log.position) {
+                Ok(read) => read,
+                Err(error) => {
+                    warn!("failed to read log for {:?}: {}", log.directory, error);
+                    continue;
+                }
+            };
+
+            for line in lines {
+                print_line(&log.label, &line, pattern.as_ref());
+            }
+
+            log.position = position;
+        }
+
+        thread::sleep(POLL_INTERVAL);
+    }
+}
+
+fn is_match(line: &str, pattern: Option<&Regex>) -> bool {
+    pattern.is_none_or(|pattern| pattern.is_match(line))
+}
+
+fn print_line(label: &str, line: &str, pattern: Option<&Regex>) {
+    if is_match(line, pattern) {
+        println!("{} | {}", label, line);
+    }
+}
@@ -1,16 +1,21 @@
-use std::process::Command;
-use std::time::Duration;
+use std::fs;
+use std::io;
+use std::path::Path;
+use std::time::{Duration, Instant};
 
-use cmd_lib::run_cmd;
 use color_eyre::Result;
 use tracing::{error, info, warn};
-use wait_timeout::ChildExt;
 
-use crate::arg_types::ServerMemory;
-use crate::config::{GlobalArgs, StartArgs};
+use crate::arg_types::{Backend, ReadyCheck, ServerMemory};
+use crate::config::{GlobalArgs, Readiness, StartArgs};
+use crate::process_backend::StopMethod;
+use crate::rcon::{RconClient, RconSettings};
+use crate::server_jar::JarManifest;
+use crate::server_list_ping;
+use crate::server_log::{self, LogPosition};
 use crate::utils;
 
-fn generate_jvm_args(args: StartArgs) -> String {
+pub fn generate_jvm_args(args: StartArgs) -> String {
     let mut flags = vec![];
     flags.push(format!("-Xmx{}", args.max_memory));
 
@@ -55,46 +60,117 @@ fn generate_jvm_args(args: StartArgs) -> String {
     flags.join(" ")
 }
 
-pub fn start(global_args: GlobalArgs, args: StartArgs) -> Result<()> {
+/// Result of starting, stopping or restarting a server, shown in the summary
+#[derive(Debug)]
+pub struct Outcome {
+    pub ok: bool,
+    pub message: String,
+}
+
+impl Outcome {
+    fn ok(message: impl Into<String>) -> Self {
+        Self {
+            ok: true,
+            message: message.into(),
+        }
+    }
+
+    fn failed(message: impl Into<String>) -> Self {
+        Self {
+            ok: false,
+            message: message.into(),
+        }
+    }
+}
+
+/// Runs `action` on each server concurrently, then prints how each one went
+///
+/// With a `batch_size`, servers are handled a batch at a time, and the remaining batches are
+/// skipped once any server in a batch fails
+fn for_each_server<F>(global_args: &GlobalArgs, batch_size: Option<usize>, action: F)
+where
+    F: Fn(&str, u16) -> Outcome + Sync,
+{
     let server_iter = utils::server_iter(
         global_args.server_count,
         global_args.start_port,
         &global_args.directory_template,
     );
 
-    let jvm_args = generate_jvm_args(args);
-    for (_, _, directory, _) in server_iter {
-        let name = directory.to_str().unwrap();
-        info!("starting tmux session: {}", &name);
-
-        if run_cmd!(tmux new -d  -s $name).is_err() {
-            error!("failed to start \"{}\"", &name);
-            continue;
-        }
-
-        let cd = format!("cd ./{}", &name);
-        if run_cmd!(tmux send -t $name $cd ENTER).is_err() {
-            error!("failed to start \"{}\"", &name);
-            continue;
+    let mut remaining = server_iter
+        .map(|(_, port, directory, _)| (port, directory))
+        .collect::<Vec<_>>();
+
+    let batch_size = batch_size.unwrap_or(remaining.len()).max(1);
+    let mut outcomes = Vec::with_capacity(remaining.len());
+    while !remaining.is_empty() {
+        let rest = remaining.split_off(batch_size.min(remaining.len()));
+        let batch = std::mem::replace(&mut remaining, rest);
+
+        let results = utils::run_parallel(batch, global_args.parallelism, |(port, directory)| {
+            let name = directory.to_str().unwrap();
+            let started_at = Instant::now();
+            let outcome = action(name, port);
+
+            (directory, outcome, started_at.elapsed())
+        });
+
+        let batch_failed = results.iter().any(|(_, outcome, _)| !outcome.ok);
+        outcomes.extend(results);
+
+        if batch_failed && !remaining.is_empty() {
+            error!(
+                "aborting, skipping {} server(s) after a failure",
+                remaining.len()
+            );
+
+            let skipped = remaining
+                .drain(..)
+                .map(|(_, directory)| (directory, Outcome::failed("skipped"), Duration::ZERO));
+            outcomes.extend(skipped);
         }
+    }
 
-        let run = format!(
-            "java {} -jar {} nogui ; tmux wait -S {}_exit",
-            &jvm_args,
-            global_args.jar_type.file_name(),
-            &name
+    println!("{:<32} {:<8} RESULT", "SERVER", "TIME");
+    for (directory, outcome, elapsed) in &outcomes {
+        println!(
+            "{:<32} {:<8} {}",
+            directory.to_str().unwrap(),
+            utils::format_duration(elapsed.as_secs()),
+            outcome.message
         );
+    }
 
-        if run_cmd!(tmux send -t $name $run ENTER).is_err() {
-            error!("failed to start \"{}\"", &name);
-            continue;
-        }
+    let failed = outcomes
+        .iter()
+        .filter(|(_, outcome, _)| !outcome.ok)
+        .count();
+    if failed > 0 {
+        error!("{} server(s) failed", failed);
+        std::process::exit(1);
     }
+}
 
-    Ok(())
+/// Optionally waits for a server that was just (re)started to pass its ready check
+fn ready_outcome(
+    name: &str,
+    port: u16,
+    message: &str,
+    wait: Option<&Readiness>,
+    since: Option<LogPosition>,
+) -> Outcome {
+    match wait {
+        None => Outcome::ok(message),
+        Some(readiness) if wait_for_server(name, port, readiness, since) => {
+            Outcome::ok(format!("{}, ready", message))
+        }
+
+        Some(_) => Outcome::failed(format!("{}, not ready", message)),
+    }
 }
 
-pub fn stop(global_args: GlobalArgs) -> Result<()> {
+/// Starts each server, optionally waiting until they pass a ready check
+pub fn start(global_args: GlobalArgs, args: StartArgs, wait: Option<Readiness>) -> Result<()> {
     let server_iter = utils::server_iter(
         global_args.server_count,
         global_args.start_port,
@@ -102,101 +178,322 @@ pub fn stop(global_args: GlobalArgs) -> Result<()> {
     );
 
     for (_, _, directory, _) in server_iter {
-        let name = directory.to_str().unwrap();
+        if let Some(manifest) = JarManifest::read(&directory)? {
+            if !manifest.verify(&directory)? {
+                warn!(
+                    "server .jar for \"{}\" does not match its recorded sha256",
+                    directory.to_str().unwrap()
+                );
+            }
+        }
+    }
 
-        info!("gracefully stopping tmux session: {}", &name);
-        if run_cmd!(tmux send -t $name C-c).is_err() {
-            error!("failed to stop \"{}\"", &name);
-            continue;
+    let jvm_args = generate_jvm_args(args);
+    let jar_file = global_args.jar_type.file_name();
+    let backend = global_args.backend;
+    for_each_server(&global_args, None, |name, port| {
+        // A session alone doesn't mean the server is up, it may have crashed inside it
+        if backend.sessions().java_pid(name).is_some() {
+            return Outcome::ok("already running");
         }
 
-        // Wait for server to shut down
-        let exit_handle = format!("{}_exit", &name);
-        let mut child = match Command::new("tmux").arg("wait").arg(exit_handle).spawn() {
-            Ok(child) => child,
-            Err(_) => {
-                error!("failed to stop \"{}\"", &name);
-                continue;
-            }
-        };
-
-        // After N seconds, timeout and kill anyway
-        let wait_duration = Duration::from_secs(u64::from(global_args.timeout_secs));
-        match child.wait_timeout(wait_duration) {
-            Err(_) => {
-                error!("failed to stop \"{}\"", &name);
-                continue;
-            }
+        let since = LogPosition::end_of(Path::new(name));
+        match start_server(backend, name, &jvm_args, &jar_file) {
+            true => ready_outcome(name, port, "started", wait.as_ref(), since),
+            false => Outcome::failed("failed to start"),
+        }
+    });
 
-            Ok(None) => warn!("reached wait timeout, forcefully killing: {}", &name),
-            _ => (),
+    Ok(())
+}
+
+pub fn stop(global_args: GlobalArgs) -> Result<()> {
+    let backend = global_args.backend;
+    for_each_server(&global_args, None, |name, _| {
+        if !backend.sessions().session_exists(name) {
+            return Outcome::ok("not running");
         }
 
-        if run_cmd!(tmux kill-session -t $name).is_err() {
-            error!("failed to stop \"{}\"", &name);
-            continue;
+        stop_server(backend, name, global_args.timeout_secs)
+    });
+
+    Ok(())
+}
+
+/// Restarts each server, optionally waiting until they pass a ready check
+///
+/// With a `batch_size`, only that many servers are restarted at once, and each batch must be
+/// ready before the next is restarted
+pub fn restart(
+    global_args: GlobalArgs,
+    args: StartArgs,
+    wait: Option<Readiness>,
+    batch_size: Option<usize>,
+) -> Result<()> {
+    let jvm_args = generate_jvm_args(args);
+    let jar_file = global_args.jar_type.file_name();
+    for_each_server(&global_args, batch_size, |name, port| {
+        let since = LogPosition::end_of(Path::new(name));
+        let outcome = restart_server(
+            global_args.backend,
+            name,
+            &jvm_args,
+            &jar_file,
+            global_args.timeout_secs,
+        );
+        match outcome.ok {
+            true => ready_outcome(name, port, &outcome.message, wait.as_ref(), since),
+            false => outcome,
         }
+    });
+
+    Ok(())
+}
+
+/// Waits for the server to pass its ready check, returns `false` on timeout
+///
+/// Log checks only consider lines written after `since`
+pub fn wait_for_server(
+    name: &str,
+    port: u16,
+    readiness: &Readiness,
+    since: Option<LogPosition>,
+) -> bool {
+    info!(
+        "waiting for \"{}\" to pass the {} ready check on port {}",
+        name, readiness.check, port
+    );
 
-        info!("stopped tmux session: {}", &name);
+    let ready = match readiness.check {
+        ReadyCheck::Port => utils::wait_for_port(port, readiness.timeout),
+        ReadyCheck::Log => server_log::wait_for_line(
+            Path::new(name),
+            since,
+            &readiness.log_pattern,
+            readiness.timeout,
+        ),
+
+        ReadyCheck::Ping => match server_list_ping::wait_until_ready(port, readiness.timeout) {
+            Some(status) => {
+                info!(
+                    "\"{}\" answered ping: {} (protocol {}), {}/{} players, motd \"{}\"",
+                    name,
+                    status.version.name,
+                    status.version.protocol,
+                    status.players.online,
+                    status.players.max,
+                    status.motd()
+                );
+
+                true
+            }
+
+            None => false,
+        },
+    };
+
+    match ready {
+        true => info!("\"{}\" is ready", name),
+        false => error!(
+            "\"{}\" did not pass the {} ready check within {} seconds",
+            name,
+            readiness.check,
+            readiness.timeout.as_secs()
+        ),
     }
 
-    Ok(())
+    ready
 }
 
-pub fn restart(global_args: GlobalArgs, args: StartArgs) -> Result<()> {
-    let server_iter = utils::server_iter(
-        global_args.server_count,
-        global_args.start_port,
-        &global_args.directory_template,
+// region: Sessions
+/// Written by the session's shell with the server's exit code once it exits
+const EXIT_CODE_FILE: &str = ".exit_code";
+
+/// Written before asking a server to shut down, so its exit isn't mistaken for a crash
+const STOP_REQUESTED_FILE: &str = ".stop_requested";
+
+/// How the server process in a session exited
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum ServerExit {
+    /// Shut down by `stop` or `restart`
+    Requested,
+    Code(i32),
+}
+
+/// How the server last exited, `None` while it is still running
+pub fn server_exit(name: &str) -> Option<ServerExit> {
+    let directory = Path::new(name);
+    let code = fs::read_to_string(directory.join(EXIT_CODE_FILE)).ok()?;
+
+    if directory.join(STOP_REQUESTED_FILE).exists() {
+        return Some(ServerExit::Requested);
+    }
+
+    // Not parsing means the shell is still writing it
+    code.trim().parse().ok().map(ServerExit::Code)
+}
+
+/// Runs the server in its existing session
+pub fn run_server(backend: Backend, name: &str, jvm_args: &str, jar_file: &str) -> bool {
+    let directory = Path::new(name);
+    for marker in [EXIT_CODE_FILE, STOP_REQUESTED_FILE] {
+        if let Err(error) = fs::remove_file(directory.join(marker)) {
+            if error.kind() != io::ErrorKind::NotFound {
+                error!("failed to clear {} for \"{}\": {}", marker, name, error);
+                return false;
+            }
+        }
+    }
+
+    let run = format!(
+        "java {} -jar {} nogui ; echo $? > {}",
+        jvm_args, jar_file, EXIT_CODE_FILE
     );
 
-    let jvm_args = generate_jvm_args(args);
-    for (_, _, directory, _) in server_iter {
-        let name = directory.to_str().unwrap();
-        info!("restarting tmux session: {}", &name);
+    backend.sessions().run(name, &run)
+}
+
+/// Runs the server, in a new session unless one was left behind when it last exited
+///
+/// Returns `false` on failure
+pub fn start_server(backend: Backend, name: &str, jvm_args: &str, jar_file: &str) -> bool {
+    info!("starting {} session: {}", backend, name);
+
+    let sessions = backend.sessions();
+    let has_session = sessions.session_exists(name) || sessions.create_session(name);
+    if !has_session || !run_server(backend, name, jvm_args, jar_file) {
+        error!("failed to start \"{}\"", name);
+        return false;
+    }
 
-        if run_cmd!(tmux send -t $name C-c).is_err() {
-            error!("failed to restart \"{}\"", &name);
-            continue;
+    true
+}
+
+/// Saves and stops the server over RCON, returns `false` if RCON isn't available
+fn rcon_stop(backend: Backend, name: &str, timeout_secs: u8) -> bool {
+    let settings = match RconSettings::read(Path::new(name)) {
+        Ok(Some(settings)) => settings,
+        _ => return false,
+    };
+
+    let timeout = Duration::from_secs(u64::from(timeout_secs));
+    let result = RconClient::connect(&settings, timeout).and_then(|mut client| {
+        client.command("save-all")?;
+        Ok(client)
+    });
+
+    let mut client = match result {
+        Ok(client) => client,
+        Err(error) => {
+            warn!(
+                "rcon failed for \"{}\", falling back to {}: {}",
+                name, backend, error
+            );
+            return false;
         }
+    };
 
-        // Wait for server to shut down
-        let exit_handle = format!("{}_exit", &name);
-        let mut child = match Command::new("tmux").arg("wait").arg(exit_handle).spawn() {
-            Ok(child) => child,
-            Err(_) => {
-                error!("failed to stop \"{}\"", &name);
-                continue;
-            }
-        };
-
-        // After N seconds, timeout and restart anyway
-        let wait_duration = Duration::from_secs(u64::from(global_args.timeout_secs));
-        match child.wait_timeout(wait_duration) {
-            Err(_) => {
-                error!("failed to stop \"{}\"", &name);
-                continue;
-            }
+    // The server can close the connection before it replies
+    let _ = client.command("stop");
+    true
+}
 
-            Ok(None) => {
-                warn!("reached wait timeout, forcefully restarting: {}", &name);
-                warn!("please manually check that the restart was successful");
-            }
-            _ => (),
+/// Asks the server to shut down and waits for it to exit, preferring RCON over the backend's
+/// own way of stopping it
+///
+/// Returns `None` if the server couldn't be signalled, otherwise how it was stopped and whether
+/// it exited in time
+fn shutdown_server(backend: Backend, name: &str, timeout_secs: u8) -> Option<(StopMethod, bool)> {
+    if fs::write(Path::new(name).join(STOP_REQUESTED_FILE), "").is_err() {
+        warn!("failed to mark \"{}\" as stopping", name);
+    }
+
+    let method = match rcon_stop(backend, name, timeout_secs) {
+        true => StopMethod::Rcon,
+        false => backend.sessions().interrupt(name)?,
+    };
+
+    // Wait for server to shut down
+    let wait_duration = Duration::from_secs(u64::from(timeout_secs));
+    let exited = backend.sessions().wait_for_exit(name, wait_duration);
+
+    Some((method, exited))
+}
+
+/// Gracefully stops the server, killing its session after a timeout
+pub fn stop_server(backend: Backend, name: &str, timeout_secs: u8) -> Outcome {
+    info!("gracefully stopping {} session: {}", backend, name);
+
+    // After N seconds, timeout and kill anyway
+    let (method, exited) = match shutdown_server(backend, name, timeout_secs) {
+        Some(shutdown) => shutdown,
+        None => {
+            error!("failed to stop \"{}\"", name);
+            return Outcome::failed("failed to stop");
         }
+    };
 
-        let run = format!(
-            "java {} -jar {} nogui ; tmux wait -S {}_exit",
-            &jvm_args,
-            global_args.jar_type.file_name(),
-            &name
-        );
+    if !exited {
+        warn!("reached wait timeout, forcefully killing: {}", name);
+    }
+
+    if !backend.sessions().kill_session(name) {
+        error!("failed to stop \"{}\"", name);
+        return Outcome::failed("failed to stop");
+    }
+
+    info!("stopped {} session: {} (via {})", backend, name, method);
+    match exited {
+        true => Outcome::ok(format!("stopped via {}", method)),
+        false => Outcome::ok(format!(
+            "killed after timeout, stop requested via {}",
+            method
+        )),
+    }
+}
 
-        if run_cmd!(tmux send -t $name $run ENTER).is_err() {
-            error!("failed to restart \"{}\"", &name);
-            continue;
+/// Gracefully stops the server and runs it again in the same session
+pub fn restart_server(
+    backend: Backend,
+    name: &str,
+    jvm_args: &str,
+    jar_file: &str,
+    timeout_secs: u8,
+) -> Outcome {
+    info!("restarting {} session: {}", backend, name);
+
+    // After N seconds, timeout and restart anyway
+    let (method, exited) = match shutdown_server(backend, name, timeout_secs) {
+        Some(shutdown) => shutdown,
+        None => {
+            error!("failed to restart \"{}\"", name);
+            return Outcome::failed("failed to restart");
         }
+    };
+
+    match exited {
+        false => {
+            warn!(
+                "reached wait timeout after stopping via {}, forcefully restarting: {}",
+                method, name
+            );
+            warn!("please manually check that the restart was successful");
+        }
+
+        true => info!("stopped \"{}\" via {}", name, method),
     }
 
-    Ok(())
+    if !run_server(backend, name, jvm_args, jar_file) {
+        error!("failed to restart \"{}\"", name);
+        return Outcome::failed("failed to restart");
+    }
+
+    match exited {
+        true => Outcome::ok(format!("restarted, stopped via {}", method)),
+        false => Outcome::ok(format!(
+            "restarted after timeout, stop requested via {}",
+            method
+        )),
+    }
 }
+// endregion
@@ -0,0 +1,133 @@
+use std::path::PathBuf;
+use std::process::Command;
+use std::time::Duration;
+
+use color_eyre::Result;
+use serde::Serialize;
+
+use crate::config::GlobalArgs;
+use crate::server_jar::JarManifest;
+use crate::server_list_ping;
+use crate::utils;
+
+#[derive(Debug, Serialize)]
+struct ServerStatus {
+    directory: PathBuf,
+    port: u16,
+    session: bool,
+    java_pid: Option<u32>,
+    uptime_secs: Option<u64>,
+    jar: Option<JarManifest>,
+    ping: Option<PingOutput>,
+}
+
+#[derive(Debug, Serialize)]
+struct PingOutput {
+    version: String,
+    protocol: i32,
+    players_online: i32,
+    players_max: i32,
+    motd: String,
+}
+
+pub fn status(global_args: GlobalArgs, json: bool) -> Result<()> {
+    let server_iter = utils::server_iter(
+        global_args.server_count,
+        global_args.start_port,
+        &global_args.directory_template,
+    );
+
+    let mut statuses = vec![];
+    for (_, port, directory, _) in server_iter {
+        let name = directory.to_str().unwrap();
+        let session = global_args.backend.sessions().session_exists(name);
+        let java_pid = session
+            .then(|| global_args.backend.sessions().java_pid(name))
+            .flatten();
+
+        let jar = match directory.exists() {
+            true => JarManifest::read(&directory)?,
+            false => None,
+        };
+
+        statuses.push(ServerStatus {
+            port,
+            session,
+            java_pid,
+            uptime_secs: java_pid.and_then(uptime_secs),
+            jar,
+            ping: ping(port),
+            directory,
+        });
+    }
+
+    if json {
+        println!("{}", serde_json::to_string_pretty(&statuses)?);
+        return Ok(());
+    }
+
+    println!(
+        "{:<32} {:<6} {:<8} {:<8} {:<10} {:<24} PING",
+        "SERVER", "PORT", "SESSION", "JAVA", "UPTIME", "JAR"
+    );
+
+    for status in statuses {
+        let java = match status.java_pid {
+            Some(pid) => pid.to_string(),
+            None => "-".into(),
+        };
+
+        let uptime = match status.uptime_secs {
+            Some(secs) => utils::format_duration(secs),
+            None => "-".into(),
+        };
+
+        let jar = match status.jar {
+            Some(manifest) => format!(
+                "{} {} #{}",
+                manifest.jar_type, manifest.version, manifest.build
+            ),
+            None => "-".into(),
+        };
+
+        let ping = match status.ping {
+            Some(ping) => format!(
+                "{}/{} players, {} ({}), \"{}\"",
+                ping.players_online, ping.players_max, ping.version, ping.protocol, ping.motd
+            ),
+            None => "-".into(),
+        };
+
+        println!(
+            "{:<32} {:<6} {:<8} {:<8} {:<10} {:<24} {}",
+            status.directory.to_str().unwrap(),
+            status.port,
+            if status.session { "yes" } else { "no" },
+            java,
+            uptime,
+            jar,
+            ping,
+        );
+    }
+
+    Ok(())
+}
+
+fn uptime_secs(pid: u32) -> Option<u64> {
+    let pid = pid.to_string();
+    let elapsed = utils::command_output(Command::new("ps").args(["-o", "etimes=", "-p", &pid]))?;
+
+    elapsed.parse().ok()
+}
+
+fn ping(port: u16) -> Option<PingOutput> {
+    let status = server_list_ping::ping_local(port, Duration::from_secs(2)).ok()?;
+
+    Some(PingOutput {
+        motd: status.motd(),
+        version: status.version.name,
+        protocol: status.version.protocol,
+        players_online: status.players.online,
+        players_max: status.players.max,
+    })
+}
@@ -0,0 +1,188 @@
+use std::collections::VecDeque;
+use std::fs::OpenOptions;
+use std::io::Write;
+use std::path::{Path, PathBuf};
+use std::thread;
+use std::time::{Duration, Instant, SystemTime, UNIX_EPOCH};
+
+use color_eyre::Result;
+use tracing::{error, info, warn};
+
+use crate::arg_types::Backend;
+use crate::cmd_start_stop::{self, ServerExit};
+use crate::config::{GlobalArgs, StartArgs, SuperviseArgs};
+use crate::server_log;
+use crate::utils;
+
+const POLL_INTERVAL: Duration = Duration::from_secs(2);
+
+/// Appended to in each server directory whenever the server crashes
+const CRASH_LOG: &str = "crashes.log";
+
+/// Lines of `logs/latest.log` to copy into each crash log entry
+const CRASH_LOG_TAIL: usize = 50;
+
+#[derive(Debug, Default)]
+struct Supervised {
+    /// When the server crashed within the crash window, oldest first
+    crashes: VecDeque<Instant>,
+    restart_at: Option<Instant>,
+    /// Set once the server's latest exit has been dealt with
+    handled: bool,
+}
+
+/// Watches each running server, restarting crashed servers with exponential backoff
+///
+/// Servers that exit with status 0, or that were shut down by `stop` or `restart`, are left
+/// alone. A server that crashes more than `max_crashes` times within the crash window is left
+/// stopped until it is started again
+pub fn supervise(
+    global_args: GlobalArgs,
+    start_args: StartArgs,
+    args: SuperviseArgs,
+) -> Result<()> {
+    if global_args.backend == Backend::Systemd {
+        warn!("systemd already restarts crashed servers, there is nothing to supervise");
+        return Ok(());
+    }
+
+    let server_iter = utils::server_iter(
+        global_args.server_count,
+        global_args.start_port,
+        &global_args.directory_template,
+    );
+
+    let mut servers: Vec<(PathBuf, Supervised)> = server_iter
+        .map(|(_, _, directory, _)| (directory, Supervised::default()))
+        .collect();
+
+    let jvm_args = cmd_start_stop::generate_jvm_args(start_args);
+    let jar_file = global_args.jar_type.file_name();
+
+    info!(
+        "supervising {} server(s), press Ctrl-C to stop supervising",
+        servers.len()
+    );
+
+    loop {
+        for (directory, state) in &mut servers {
+            let name = directory.to_str().unwrap();
+
+            // Servers without a session were never started, or were stopped on purpose
+            if !global_args.backend.sessions().session_exists(name) {
+                state.restart_at = None;
+                state.handled = false;
+                continue;
+            }
+
+            if let Some(restart_at) = state.restart_at {
+                if Instant::now() < restart_at {
+                    continue;
+                }
+
+                state.restart_at = None;
+                info!("restarting crashed server \"{}\"", name);
+                if cmd_start_stop::run_server(global_args.backend, name, &jvm_args, &jar_file) {
+                    state.handled = false;
+                } else {
+                    error!("failed to restart \"{}\", no longer supervising it", name);
+                }
+
+                continue;
+            }
+
+            let exit = match cmd_start_stop::server_exit(name) {
+                Some(exit) => exit,
+                None => {
+                    state.handled = false;
+                    continue;
+                }
+            };
+
+            if state.handled {
+                continue;
+            }
+
+            state.handled = true;
+            match exit {
+                ServerExit::Requested => (),
+                ServerExit::Code(0) => info!("\"{}\" exited cleanly, not restarting", name),
+                ServerExit::Code(code) => on_crash(directory, state, code, &args),
+            }
+        }
+
+        thread::sleep(POLL_INTERVAL);
+    }
+}
+
+/// Records the crash, then either schedules a restart or gives up on a crash-looping server
+fn on_crash(directory: &Path, state: &mut Supervised, code: i32, args: &SuperviseArgs) {
+    let name = directory.to_str().unwrap();
+    let now = Instant::now();
+
+    while let Some(crashed_at) = state.crashes.front() {
+        match now.duration_since(*crashed_at) > args.crash_window {
+            true => state.crashes.pop_front(),
+            false => break,
+        };
+    }
+
+    state.crashes.push_back(now);
+    let crashes = state.crashes.len();
+    let giving_up = crashes > args.max_crashes;
+
+    let summary = match giving_up {
+        true => format!(
+            "exited with code {}, {} crashes within {}, not restarting",
+            code,
+            crashes,
+            utils::format_duration(args.crash_window.as_secs())
+        ),
+        false => format!("exited with code {}", code),
+    };
+
+    if let Err(error) = write_crash_log(directory, &summary) {
+        warn!("failed to write crash log for \"{}\": {}", name, error);
+    }
+
+    if giving_up {
+        error!("\"{}\" {}", name, summary);
+        state.crashes.clear();
+        return;
+    }
+
+    // Doubles with each crash in the window, e.g. 5s, 10s, 20s...
+    let exponent = (crashes - 1).min(16) as u32;
+    let backoff = args.backoff.saturating_mul(2_u32.pow(exponent));
+    let backoff = backoff.min(args.max_backoff);
+
+    warn!(
+        "\"{}\" {}, restarting in {}",
+        name,
+        summary,
+        utils::format_duration(backoff.as_secs())
+    );
+    state.restart_at = Some(now + backoff);
+}
+
+fn write_crash_log(directory: &Path, summary: &str) -> Result<()> {
+    let timestamp = SystemTime::now().duration_since(UNIX_EPOCH)?.as_secs();
+    let mut entry = format!(
+        "=== {} {} ===\n",
+        utils::format_unix_timestamp(timestamp),
+        summary
+    );
+
+    for line in server_log::tail(directory, CRASH_LOG_TAIL)? {
+        entry.push_str(&line);
+        entry.push('\n');
+    }
+
+    let mut file = OpenOptions::new()
+        .create(true)
+        .append(true)
+        .open(directory.join(CRASH_LOG))?;
+    file.write_all(entry.as_bytes())?;
+
+    Ok(())
+}
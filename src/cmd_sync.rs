@@ -1,4 +1,5 @@
 use std::fs;
+use std::path::Path;
 
 use color_eyre::Result;
 use fs_extra::dir::{self, CopyOptions};
@@ -26,31 +27,16 @@ pub fn sync(global_args: GlobalArgs, clear_plugins: bool) -> Result<()> {
     for (_, _, directory, _) in server_iter {
         let name = directory.to_str().unwrap();
 
-        // Clear plugins dir
+        // Clear plugins and mods dirs
         if clear_plugins {
-            let server_plugins_dir = directory.join("plugins");
-            if !server_plugins_dir.exists() {
-                continue;
-            }
-
-            info!("clearing plugins dir in server: {}", &name);
-            for entry in fs::read_dir(server_plugins_dir)? {
-                let entry = entry?;
-                let file_type = entry.file_type()?;
-
-                if !file_type.is_file() {
+            for dir in utils::NAMED_SYNC_DIRS {
+                let server_dir = directory.join(dir);
+                if !server_dir.exists() {
                     continue;
                 }
 
-                let path = entry.path();
-                let extension = match path.extension() {
-                    None => continue,
-                    Some(extension) => extension.to_string_lossy().to_lowercase(),
-                };
-
-                if extension == "jar" {
-                    fs::remove_file(&path)?;
-                }
+                info!("clearing {} dir in server: {}", dir, &name);
+                clear_jars(&server_dir)?;
             }
         }
 
@@ -66,15 +52,7 @@ pub fn sync(global_args: GlobalArgs, clear_plugins: bool) -> Result<()> {
                 continue;
             }
 
-            let is_plugins_dir = match source_dir.file_name() {
-                Some(dir) => dir == "plugins",
-                _ => false,
-            };
-
-            let target_dir = match is_plugins_dir {
-                true => directory.join("plugins"),
-                false => directory.clone(),
-            };
+            let target_dir = utils::sync_target_dir(source_dir, &directory);
 
             dir::copy(&source_dir, &target_dir, &options)?;
         }
@@ -82,3 +60,26 @@ pub fn sync(global_args: GlobalArgs, clear_plugins: bool) -> Result<()> {
 
     Ok(())
 }
+
+fn clear_jars(dir: &Path) -> Result<()> {
+    for entry in fs::read_dir(dir)? {
+        let entry = entry?;
+        let file_type = entry.file_type()?;
+
+        if !file_type.is_file() {
+            continue;
+        }
+
+        let path = entry.path();
+        let extension = match path.extension() {
+            None => continue,
+            Some(extension) => extension.to_string_lossy().to_lowercase(),
+        };
+
+        if extension == "jar" {
+            fs::remove_file(&path)?;
+        }
+    }
+
+    Ok(())
+}
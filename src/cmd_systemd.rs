@@ -0,0 +1,285 @@
+use std::fs;
+use std::path::{Path, PathBuf};
+
+use color_eyre::Result;
+use tracing::{error, info, warn};
+
+use crate::cmd_start_stop;
+use crate::config::{GlobalArgs, StartArgs, SuperviseArgs};
+use crate::process_backend::SystemdBackend;
+use crate::utils;
+
+/// Settings shared by every server's unit
+#[derive(Debug)]
+struct UnitTemplate {
+    java: PathBuf,
+    jvm_args: String,
+    jar_file: String,
+    memory_max: u64,
+    timeout_secs: u8,
+    supervise_args: SuperviseArgs,
+}
+
+impl UnitTemplate {
+    fn new(global_args: &GlobalArgs, start_args: StartArgs, supervise_args: SuperviseArgs) -> Self {
+        let memory_max = start_args.max_memory.limit_bytes();
+
+        Self {
+            java: find_java(),
+            jvm_args: cmd_start_stop::generate_jvm_args(start_args),
+            jar_file: global_args.jar_type.file_name(),
+            memory_max,
+            timeout_secs: global_args.timeout_secs,
+            supervise_args,
+        }
+    }
+
+    fn render(&self, name: &str, working_directory: &Path) -> String {
+        let backoff_secs = self.supervise_args.backoff.as_secs();
+        let max_backoff_secs = self.supervise_args.max_backoff.as_secs();
+        let restart_steps = restart_steps(backoff_secs, max_backoff_secs);
+
+        format!(
+            "# Generated by provisioner, run `provisioner systemd install` again after changing settings
+[Unit]
+Description=Minecraft server {name}
+After=network-online.target
+Wants=network-online.target
+StartLimitIntervalSec={crash_window}
+StartLimitBurst={max_crashes}
+
+[Service]
+Type=simple
+WorkingDirectory={working_directory}
+ExecStart={java} {jvm_args} -jar {jar_file} nogui
+Restart=on-failure
+RestartSec={backoff}
+# Exponential backoff needs systemd 254 or later, older versions always wait RestartSec
+RestartSteps={restart_steps}
+RestartMaxDelaySec={max_backoff}
+# The server saves and shuts down gracefully on SIGTERM, exiting with 143
+KillMode=mixed
+KillSignal=SIGTERM
+SuccessExitStatus=130 143
+TimeoutStopSec={timeout_secs}
+MemoryMax={memory_max}
+LimitNOFILE=65536
+
+[Install]
+WantedBy=default.target
+",
+            name = name,
+            crash_window = self.supervise_args.crash_window.as_secs(),
+            max_crashes = self.supervise_args.max_crashes,
+            working_directory = working_directory.display(),
+            java = self.java.display(),
+            jvm_args = self.jvm_args,
+            jar_file = self.jar_file,
+            backoff = backoff_secs,
+            restart_steps = restart_steps,
+            max_backoff = max_backoff_secs,
+            timeout_secs = self.timeout_secs,
+            memory_max = self.memory_max,
+        )
+    }
+}
+
+/// systemd runs units with a minimal `PATH`, so resolve `java` now
+fn find_java() -> PathBuf {
+    let java = std::env::var_os("PATH").and_then(|path| {
+        std::env::split_paths(&path)
+            .map(|directory| directory.join("java"))
+            .find(|java| java.is_file())
+    });
+
+    match java {
+        Some(java) => java,
+        None => {
+            warn!("could not find java on PATH, units will use /usr/bin/java");
+            PathBuf::from("/usr/bin/java")
+        }
+    }
+}
+
+fn user_unit_dir() -> PathBuf {
+    let config_home = std::env::var_os("XDG_CONFIG_HOME")
+        .map(PathBuf::from)
+        .or_else(|| std::env::var_os("HOME").map(|home| PathBuf::from(home).join(".config")));
+
+    match config_home {
+        Some(config_home) => config_home.join("systemd/user"),
+        None => PathBuf::from(".config/systemd/user"),
+    }
+}
+
+/// Writes a unit for each server into `output_dir`, returning the unit names
+fn write_units(
+    global_args: &GlobalArgs,
+    template: &UnitTemplate,
+    output_dir: &Path,
+) -> Result<Vec<String>> {
+    fs::create_dir_all(output_dir)?;
+    let current_dir = std::env::current_dir()?;
+
+    let server_iter = utils::server_iter(
+        global_args.server_count,
+        global_args.start_port,
+        &global_args.directory_template,
+    );
+
+    let mut units = vec![];
+    for (_, _, directory, _) in server_iter {
+        let name = directory.to_str().unwrap();
+        if !directory.exists() {
+            warn!("server \"{}\" does not exist, skipping", name);
+            continue;
+        }
+
+        let unit = SystemdBackend::unit_name(name);
+        let contents = template.render(name, &current_dir.join(&directory));
+        fs::write(output_dir.join(&unit), contents)?;
+
+        info!("wrote {:?}", output_dir.join(&unit));
+        units.push(unit);
+    }
+
+    Ok(units)
+}
+
+/// Writes a systemd unit for each server into `output_dir`
+pub fn generate(
+    global_args: GlobalArgs,
+    start_args: StartArgs,
+    supervise_args: SuperviseArgs,
+    output_dir: &Path,
+) -> Result<()> {
+    let template = UnitTemplate::new(&global_args, start_args, supervise_args);
+    write_units(&global_args, &template, output_dir)?;
+
+    Ok(())
+}
+
+/// Installs a user unit for each server, optionally enabling them to start on login
+pub fn install(
+    global_args: GlobalArgs,
+    start_args: StartArgs,
+    supervise_args: SuperviseArgs,
+    enable: bool,
+) -> Result<()> {
+    let template = UnitTemplate::new(&global_args, start_args, supervise_args);
+    let units = write_units(&global_args, &template, &user_unit_dir())?;
+
+    if !SystemdBackend::systemctl(&["daemon-reload"]) {
+        error!("failed to reload systemd, is a user instance of systemd running?");
+        std::process::exit(1);
+    }
+
+    if enable {
+        let mut args = vec!["enable"];
+        args.extend(units.iter().map(String::as_str));
+
+        if !SystemdBackend::systemctl(&args) {
+            error!("failed to enable units");
+            std::process::exit(1);
+        }
+    }
+
+    info!(
+        "installed {} unit(s), use `--backend systemd` to manage them with provisioner",
+        units.len()
+    );
+
+    Ok(())
+}
+
+/// Stops, disables and removes each server's user unit
+pub fn uninstall(global_args: GlobalArgs) -> Result<()> {
+    let unit_dir = user_unit_dir();
+    let server_iter = utils::server_iter(
+        global_args.server_count,
+        global_args.start_port,
+        &global_args.directory_template,
+    );
+
+    for (_, _, directory, _) in server_iter {
+        let unit = SystemdBackend::unit_name(directory.to_str().unwrap());
+        let path = unit_dir.join(&unit);
+        if !path.exists() {
+            continue;
+        }
+
+        if !SystemdBackend::systemctl(&["disable", "--now", &unit]) {
+            warn!("failed to stop and disable {}", unit);
+        }
+
+        fs::remove_file(&path)?;
+        info!("removed {:?}", path);
+    }
+
+    SystemdBackend::systemctl(&["daemon-reload"]);
+    Ok(())
+}
+
+/// Number of doublings to get from the first delay to the longest one
+fn restart_steps(backoff_secs: u64, max_backoff_secs: u64) -> u32 {
+    let mut delay_secs = backoff_secs;
+    let mut steps = 0;
+    while delay_secs > 0 && delay_secs < max_backoff_secs {
+        delay_secs = delay_secs.saturating_mul(2);
+        steps += 1;
+    }
+
+    steps
+}
+
+#[cfg(test)]
+mod tests {
+    use std::time::Duration;
+
+    use super::*;
+
+    #[test]
+    fn test_render_unit() {
+        let template = UnitTemplate {
+            java: PathBuf::from("/usr/bin/java"),
+            jvm_args: "-Xmx2G".into(),
+            jar_file: "paper.jar".into(),
+            memory_max: 2684354560,
+            timeout_secs: 10,
+            supervise_args: SuperviseArgs {
+                max_crashes: 5,
+                crash_window: Duration::from_secs(600),
+                backoff: Duration::from_secs(5),
+                max_backoff: Duration::from_secs(300),
+            },
+        };
+
+        let unit = template.render(
+            "mammoth_server_25565",
+            Path::new("/srv/mammoth/mammoth_server_25565"),
+        );
+
+        assert!(unit.contains("WorkingDirectory=/srv/mammoth/mammoth_server_25565\n"));
+        assert!(unit.contains("ExecStart=/usr/bin/java -Xmx2G -jar paper.jar nogui\n"));
+        assert!(unit.contains("StartLimitBurst=5\n"));
+        assert!(unit.contains("RestartSec=5\n"));
+
+        // 5s doubled 6 times passes the 300s limit
+        assert!(unit.contains("RestartSteps=6\n"));
+        assert!(unit.contains("MemoryMax=2684354560\n"));
+        assert!(unit.contains("TimeoutStopSec=10\n"));
+    }
+
+    #[test]
+    fn test_restart_steps() {
+        assert_eq!(restart_steps(5, 300), 6);
+        assert_eq!(restart_steps(5, 5), 0);
+        assert_eq!(restart_steps(300, 5), 0);
+
+        // Without a first delay there is nothing to double
+        assert_eq!(restart_steps(0, 300), 0);
+
+        // Doubling stops at the largest delay rather than overflowing
+        assert_eq!(restart_steps(3, u64::MAX), 63);
+    }
+}
@@ -1,29 +1,246 @@
-use std::fs;
+use std::path::{Path, PathBuf};
 
 use color_eyre::Result;
+use tracing::{error, info, warn};
 
-use crate::config::GlobalArgs;
+use crate::arg_types::JarType;
+use crate::cmd_start_stop;
+use crate::config::{GlobalArgs, Readiness, StartArgs};
+use crate::server_jar::{self, JarBackup, JarCache, JarManifest, ServerJarError};
+use crate::server_log::LogPosition;
 use crate::utils;
 
-pub fn update_server(global_args: GlobalArgs) -> Result<()> {
-    let server_jar = global_args.jar_type.download(&global_args.jar_version)?;
+#[derive(Debug)]
+pub struct RollingArgs {
+    pub start_args: StartArgs,
+    pub readiness: Readiness,
+}
+
+/// Existing server directories, their port, and whether they are currently running
+type ServerState = (u16, PathBuf, bool);
 
+fn server_states(global_args: &GlobalArgs) -> Vec<ServerState> {
     let server_iter = utils::server_iter(
         global_args.server_count,
         global_args.start_port,
         &global_args.directory_template,
     );
 
-    for (_, _, directory, _) in server_iter {
-        if !directory.exists() {
+    server_iter
+        .filter(|(_, _, directory, _)| directory.exists())
+        .map(|(_, port, directory, _)| {
+            let running = global_args
+                .backend
+                .sessions()
+                .session_exists(directory.to_str().unwrap());
+            (port, directory, running)
+        })
+        .collect()
+}
+
+/// Exits if any server is running, unless they are being replaced one at a time
+fn refuse_running(servers: &[ServerState], rolling: &Option<RollingArgs>) {
+    let running: Vec<_> = servers
+        .iter()
+        .filter(|(_, _, running)| *running)
+        .map(|(_, directory, _)| directory.to_str().unwrap())
+        .collect();
+
+    if !running.is_empty() && rolling.is_none() {
+        error!(
+            "refusing to replace the server .jar of running servers: {}",
+            running.join(", ")
+        );
+        error!("stop them first, or use --rolling to replace them one at a time");
+        std::process::exit(1);
+    }
+}
+
+/// Replaces each server's .jar, stopping and starting running servers one at a time
+///
+/// `replace` returns the type of the .jar it left in place, which is the one started again
+fn replace_jars<F>(
+    global_args: &GlobalArgs,
+    servers: Vec<ServerState>,
+    rolling: Option<RollingArgs>,
+    mut replace: F,
+) -> Result<()>
+where
+    F: FnMut(&Path) -> Result<JarType>,
+{
+    let rolling = rolling.map(|rolling| {
+        let jvm_args = cmd_start_stop::generate_jvm_args(rolling.start_args);
+        (jvm_args, rolling.readiness)
+    });
+
+    for (port, directory, running) in servers {
+        let name = directory.to_str().unwrap();
+        if running
+            && !cmd_start_stop::stop_server(global_args.backend, name, global_args.timeout_secs).ok
+        {
+            error!("aborting rolling update, \"{}\" could not be stopped", name);
+            std::process::exit(1);
+        }
+
+        let jar_type = replace(&directory)?;
+        if !running {
             continue;
         }
 
-        fs::write(
-            directory.join(global_args.jar_type.file_name()),
+        // Running servers are only left at this point with --rolling
+        let (jvm_args, readiness) = rolling.as_ref().unwrap();
+        let since = LogPosition::end_of(&directory);
+        if !cmd_start_stop::start_server(global_args.backend, name, jvm_args, &jar_type.file_name())
+        {
+            error!("aborting rolling update, \"{}\" could not be started", name);
+            std::process::exit(1);
+        }
+
+        if !cmd_start_stop::wait_for_server(name, port, readiness, since) {
+            error!("aborting rolling update, \"{}\" did not come back", name);
+            std::process::exit(1);
+        }
+    }
+
+    Ok(())
+}
+
+/// Installs the latest (or pinned) build into each server directory
+///
+/// Running servers are refused unless `rolling` is set, in which case each one is stopped,
+/// updated and started again before moving on to the next
+pub fn update_server(global_args: GlobalArgs, rolling: Option<RollingArgs>) -> Result<()> {
+    let servers = server_states(&global_args);
+    refuse_running(&servers, &rolling);
+
+    let cache = JarCache::new(&global_args.cache_dir);
+    let server_jar = global_args.jar_type.download(
+        &global_args.jar_version,
+        global_args.jar_build.as_deref(),
+        &global_args.jar_options,
+        &cache,
+    )?;
+
+    replace_jars(&global_args, servers, rolling, |directory| {
+        server_jar::install_jar(
+            directory,
+            global_args.jar_type,
+            &global_args.jar_version,
             &server_jar,
         )?;
+
+        Ok(global_args.jar_type)
+    })
+}
+
+/// Restores the most recently backed up build in each server directory
+pub fn rollback_server(global_args: GlobalArgs, rolling: Option<RollingArgs>) -> Result<()> {
+    let mut servers = server_states(&global_args);
+    servers.retain(|(_, directory, _)| match JarBackup::list(directory) {
+        Ok(backups) if !backups.is_empty() => true,
+        Ok(_) => {
+            warn!("no previous server .jar to roll back to in {:?}", directory);
+            false
+        }
+
+        Err(error) => {
+            error!("failed to read server .jar backups in {:?}", directory);
+            error!("{}", error);
+            false
+        }
+    });
+
+    refuse_running(&servers, &rolling);
+    replace_jars(&global_args, servers, rolling, |directory| {
+        let backup = JarBackup::list(directory)?.pop().unwrap();
+        let manifest = backup.restore(directory)?;
+
+        info!(
+            "rolled back {:?} to {} build {} for {}",
+            directory, manifest.jar_type, manifest.build, manifest.version
+        );
+
+        // The restored build may be of a different type to the one currently configured
+        Ok(manifest.jar_type)
+    })
+}
+
+/// Compares each server's installed build against the build `update-server` would install,
+/// exiting with status 2 if any server is out of date
+pub fn check(global_args: GlobalArgs) -> Result<()> {
+    if global_args.jar_type == JarType::Custom {
+        return Err(ServerJarError::NoBuildApi(JarType::Custom).into());
+    }
+
+    let latest = global_args.jar_type.resolve_build(
+        &global_args.jar_version,
+        global_args.jar_build.as_deref(),
+        &global_args.jar_options,
+    )?;
+
+    let server_iter = utils::server_iter(
+        global_args.server_count,
+        global_args.start_port,
+        &global_args.directory_template,
+    );
+
+    println!(
+        "{:<32} {:<24} {:<24} STATUS",
+        "SERVER", "INSTALLED", "LATEST"
+    );
+
+    let mut outdated = 0;
+    for (_, _, directory, _) in server_iter {
+        let name = directory.to_str().unwrap();
+        let latest_label = format!(
+            "{} {} #{}",
+            global_args.jar_type, global_args.jar_version, latest.build
+        );
+
+        if !directory.exists() {
+            println!("{:<32} {:<24} {:<24} missing", name, "-", latest_label);
+            continue;
+        }
+
+        let (installed_label, status) = match JarManifest::read(&directory)? {
+            None => {
+                outdated += 1;
+                ("unknown".to_owned(), "update available")
+            }
+
+            Some(manifest) => {
+                let label = format!(
+                    "{} {} #{}",
+                    manifest.jar_type, manifest.version, manifest.build
+                );
+
+                let up_to_date = manifest.jar_type == global_args.jar_type
+                    && manifest.version == global_args.jar_version
+                    && manifest.build == latest.build;
+
+                if !manifest.verify(&directory)? {
+                    outdated += 1;
+                    (label, "modified")
+                } else if up_to_date {
+                    (label, "up to date")
+                } else {
+                    outdated += 1;
+                    (label, "update available")
+                }
+            }
+        };
+
+        println!(
+            "{:<32} {:<24} {:<24} {}",
+            name, installed_label, latest_label, status
+        );
+    }
+
+    if outdated > 0 {
+        warn!("{} server(s) need updating", outdated);
+        std::process::exit(2);
     }
 
+    info!("all servers are up to date");
     Ok(())
 }
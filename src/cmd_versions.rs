@@ -0,0 +1,75 @@
+use color_eyre::Result;
+use serde::Serialize;
+
+use crate::arg_types::JarType;
+use crate::config::GlobalArgs;
+use crate::server_jar::BuildInfo;
+
+#[derive(Debug, Serialize)]
+struct VersionsOutput {
+    jar_type: JarType,
+    versions: Vec<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    version: Option<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    builds: Option<Vec<BuildInfo>>,
+}
+
+pub fn versions(global_args: GlobalArgs, limit: usize, json: bool) -> Result<()> {
+    let jar_type = global_args.jar_type;
+    let versions = jar_type.versions()?;
+
+    // Builds are only listed once a game version has been picked
+    let (version, builds) = if global_args.jar_version.is_empty() {
+        (None, None)
+    } else {
+        let builds = jar_type.builds(&global_args.jar_version, limit, &global_args.jar_options)?;
+
+        (Some(global_args.jar_version), Some(builds))
+    };
+
+    let output = VersionsOutput {
+        jar_type,
+        versions,
+        version,
+        builds,
+    };
+
+    if json {
+        println!("{}", serde_json::to_string_pretty(&output)?);
+        return Ok(());
+    }
+
+    println!(
+        "{} versions: {}",
+        output.jar_type,
+        output.versions.join(", ")
+    );
+
+    if let (Some(version), Some(builds)) = (output.version, output.builds) {
+        println!();
+        println!("recent {} builds for {}:", output.jar_type, version);
+        println!("{:<16} {:<26} CHANGES", "BUILD", "TIME");
+
+        for build in builds {
+            let time = build.time.as_deref().unwrap_or("-");
+            let mut changes = build
+                .changes
+                .iter()
+                .filter_map(|change| change.lines().next());
+
+            println!(
+                "{:<16} {:<26} {}",
+                build.build,
+                time,
+                changes.next().unwrap_or("-")
+            );
+
+            for change in changes {
+                println!("{:<16} {:<26} {}", "", "", change);
+            }
+        }
+    }
+
+    Ok(())
+}
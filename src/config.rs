@@ -1,10 +1,14 @@
 use std::collections::{BTreeMap, HashSet};
 use std::path::PathBuf;
+use std::time::Duration;
 
 use color_eyre::Result;
+use regex::Regex;
 use serde::Deserialize;
 
-use crate::arg_types::{self, JarType, ServerMemory, ServerProperty};
+use crate::arg_types::{self, Backend, JarType, ReadyCheck, ServerMemory, ServerProperty};
+use crate::server_jar::http::{ApiUrls, HttpOptions};
+use crate::server_jar::JarOptions;
 use crate::Args;
 
 // region: TOML
@@ -13,19 +17,41 @@ pub struct Config {
     pub global: Option<GlobalConfig>,
     pub init: Option<InitConfig>,
     pub start: Option<StartConfig>,
+    pub supervise: Option<SuperviseConfig>,
+    pub export: Option<ExportConfig>,
     pub world_management: Option<WorldManagementConfig>,
+    pub http: Option<HttpConfig>,
 }
 
 #[derive(Debug, Default, Deserialize)]
 pub struct GlobalConfig {
     jar_type: Option<JarType>,
     jar_version: Option<String>,
+    jar_build: Option<String>,
     server_count: Option<u8>,
     start_port: Option<u16>,
     level_name: Option<String>,
     directory_template: Option<String>,
     sync_dirs: Option<Vec<PathBuf>>,
     timeout_secs: Option<u8>,
+    parallelism: Option<usize>,
+    backend: Option<Backend>,
+    cache_dir: Option<PathBuf>,
+    fabric_loader_version: Option<String>,
+    fabric_installer_version: Option<String>,
+    jar_source: Option<String>,
+    jar_checksum: Option<String>,
+}
+
+#[derive(Debug, Default, Deserialize)]
+pub struct HttpConfig {
+    proxy: Option<String>,
+    timeout_secs: Option<u64>,
+    paper_url: Option<String>,
+    pufferfish_url: Option<String>,
+    purpur_url: Option<String>,
+    vanilla_url: Option<String>,
+    fabric_url: Option<String>,
 }
 
 #[derive(Debug, Default, Deserialize)]
@@ -34,6 +60,9 @@ pub struct InitConfig {
     ops: Option<Vec<String>>,
     white_list: Option<Vec<String>>,
     server_properties: Option<BTreeMap<String, String>>,
+    enable_rcon: Option<bool>,
+    rcon_start_port: Option<u16>,
+    rcon_password: Option<String>,
 }
 
 #[derive(Debug, Default, Deserialize)]
@@ -41,6 +70,25 @@ pub struct StartConfig {
     max_memory: Option<ServerMemory>,
     use_aikar_flags: Option<bool>,
     jvm_args: Option<String>,
+    ready_check: Option<ReadyCheck>,
+    ready_log_pattern: Option<String>,
+    startup_timeout_secs: Option<u16>,
+}
+
+#[derive(Debug, Default, Deserialize)]
+pub struct SuperviseConfig {
+    max_crashes: Option<usize>,
+    crash_window_secs: Option<u64>,
+    backoff_secs: Option<u64>,
+    max_backoff_secs: Option<u64>,
+}
+
+#[derive(Debug, Default, Deserialize)]
+pub struct ExportConfig {
+    image: Option<String>,
+    proxy_image: Option<String>,
+    proxy_port: Option<u16>,
+    storage_size: Option<String>,
 }
 
 #[derive(Debug, Default, Deserialize)]
@@ -70,12 +118,17 @@ pub fn read_config() -> Result<Config> {
 pub struct GlobalArgs {
     pub jar_type: JarType,
     pub jar_version: String,
+    pub jar_build: Option<String>,
     pub server_count: u8,
     pub start_port: u16,
     pub level_name: String,
     pub directory_template: String,
     pub sync_dirs: Vec<PathBuf>,
     pub timeout_secs: u8,
+    pub parallelism: usize,
+    pub backend: Backend,
+    pub cache_dir: PathBuf,
+    pub jar_options: JarOptions,
 }
 
 pub fn global_args(config: GlobalConfig, args: Args) -> GlobalArgs {
@@ -90,6 +143,7 @@ pub fn global_args(config: GlobalConfig, args: Args) -> GlobalArgs {
     GlobalArgs {
         jar_type: args.jar_type.or(config.jar_type).unwrap_or_default(),
         jar_version: args.jar_version.or(config.jar_version).unwrap_or_default(),
+        jar_build: args.jar_build.or(config.jar_build),
         server_count: args.server_count.or(config.server_count).unwrap_or(2),
         start_port: args.start_port.or(config.start_port).unwrap_or(25565),
         level_name: args
@@ -102,6 +156,64 @@ pub fn global_args(config: GlobalConfig, args: Args) -> GlobalArgs {
             .unwrap_or_else(|| "Mammoth Server".into()),
         sync_dirs,
         timeout_secs: args.timeout_secs.or(config.timeout_secs).unwrap_or(10),
+        parallelism: args
+            .parallelism
+            .or(config.parallelism)
+            .unwrap_or(usize::MAX)
+            .max(1),
+        backend: args.backend.or(config.backend).unwrap_or_default(),
+        cache_dir: args
+            .cache_dir
+            .or(config.cache_dir)
+            .unwrap_or_else(default_cache_dir),
+        jar_options: JarOptions {
+            fabric_loader_version: args.fabric_loader_version.or(config.fabric_loader_version),
+            fabric_installer_version: args
+                .fabric_installer_version
+                .or(config.fabric_installer_version),
+            jar_source: args.jar_source.or(config.jar_source),
+            jar_checksum: args.jar_checksum.or(config.jar_checksum),
+        },
+    }
+}
+
+fn default_cache_dir() -> PathBuf {
+    let cache_home = std::env::var_os("XDG_CACHE_HOME")
+        .map(PathBuf::from)
+        .or_else(|| std::env::var_os("HOME").map(|home| PathBuf::from(home).join(".cache")));
+
+    match cache_home {
+        Some(cache_home) => cache_home.join(env!("CARGO_PKG_NAME")),
+        None => PathBuf::from(".provisioner_cache"),
+    }
+}
+
+/// API URLs can be overridden with `PROVISIONER_<PROVIDER>_URL` environment variables,
+/// which take priority over the config file
+pub fn http_options(config: HttpConfig) -> HttpOptions {
+    fn url(env_var: &str, config: Option<String>, default: String) -> String {
+        std::env::var(env_var).ok().or(config).unwrap_or(default)
+    }
+
+    let defaults = ApiUrls::default();
+    HttpOptions {
+        proxy: config.proxy,
+        timeout_secs: config.timeout_secs,
+        api_urls: ApiUrls {
+            paper: url("PROVISIONER_PAPER_URL", config.paper_url, defaults.paper),
+            pufferfish: url(
+                "PROVISIONER_PUFFERFISH_URL",
+                config.pufferfish_url,
+                defaults.pufferfish,
+            ),
+            purpur: url("PROVISIONER_PURPUR_URL", config.purpur_url, defaults.purpur),
+            vanilla: url(
+                "PROVISIONER_VANILLA_URL",
+                config.vanilla_url,
+                defaults.vanilla,
+            ),
+            fabric: url("PROVISIONER_FABRIC_URL", config.fabric_url, defaults.fabric),
+        },
     }
 }
 
@@ -111,6 +223,13 @@ pub struct InitArgs {
     pub ops: HashSet<String>,
     pub white_list: HashSet<String>,
     pub server_properties: Vec<ServerProperty>,
+    pub rcon: Option<RconArgs>,
+}
+
+#[derive(Debug)]
+pub struct RconArgs {
+    pub start_port: Option<u16>,
+    pub password: Option<String>,
 }
 
 #[allow(clippy::too_many_arguments)]
@@ -120,6 +239,8 @@ pub fn init_args(
     mut ops: Vec<String>,
     mut white_list: Vec<String>,
     server_properties: Vec<ServerProperty>,
+    enable_rcon: Option<bool>,
+    rcon_start_port: Option<u16>,
 ) -> Result<InitArgs> {
     let ops = {
         let mut config_ops = config.ops.unwrap_or_default();
@@ -162,6 +283,13 @@ pub fn init_args(
         ops,
         white_list,
         server_properties: arg_types::map_to_properties(server_properties)?,
+        rcon: enable_rcon
+            .or(config.enable_rcon)
+            .unwrap_or(false)
+            .then(|| RconArgs {
+                start_port: rcon_start_port.or(config.rcon_start_port),
+                password: config.rcon_password,
+            }),
     };
 
     Ok(args)
@@ -189,6 +317,108 @@ pub fn start_args(
     }
 }
 
+/// Matches the line vanilla and its forks log once the world has loaded
+const DEFAULT_READY_LOG_PATTERN: &str = r"Done \([0-9.,]+s\)! For help";
+
+/// How to tell that a (re)started server is ready, and how long to wait for it
+#[derive(Debug, Clone)]
+pub struct Readiness {
+    pub check: ReadyCheck,
+    pub log_pattern: Regex,
+    pub timeout: Duration,
+}
+
+pub fn readiness(
+    config: &StartConfig,
+    check: Option<ReadyCheck>,
+    log_pattern: Option<String>,
+    startup_timeout_secs: Option<u16>,
+) -> Result<Readiness> {
+    let log_pattern = log_pattern
+        .or_else(|| config.ready_log_pattern.clone())
+        .unwrap_or_else(|| DEFAULT_READY_LOG_PATTERN.into());
+
+    let timeout_secs = startup_timeout_secs
+        .or(config.startup_timeout_secs)
+        .unwrap_or(120);
+
+    Ok(Readiness {
+        check: check.or(config.ready_check).unwrap_or_default(),
+        log_pattern: Regex::new(&log_pattern)?,
+        timeout: Duration::from_secs(u64::from(timeout_secs)),
+    })
+}
+
+#[derive(Debug)]
+pub struct SuperviseArgs {
+    pub max_crashes: usize,
+    pub crash_window: Duration,
+    pub backoff: Duration,
+    pub max_backoff: Duration,
+}
+
+pub fn supervise_args(
+    config: SuperviseConfig,
+    max_crashes: Option<usize>,
+    crash_window_secs: Option<u64>,
+    backoff_secs: Option<u64>,
+    max_backoff_secs: Option<u64>,
+) -> SuperviseArgs {
+    let backoff_secs = backoff_secs.or(config.backoff_secs).unwrap_or(5).max(1);
+    let max_backoff_secs = max_backoff_secs
+        .or(config.max_backoff_secs)
+        .unwrap_or(300)
+        .max(backoff_secs);
+
+    SuperviseArgs {
+        max_crashes: max_crashes.or(config.max_crashes).unwrap_or(5),
+        crash_window: Duration::from_secs(
+            crash_window_secs
+                .or(config.crash_window_secs)
+                .unwrap_or(600),
+        ),
+        backoff: Duration::from_secs(backoff_secs),
+        max_backoff: Duration::from_secs(max_backoff_secs),
+    }
+}
+
+#[derive(Debug)]
+pub struct ExportArgs {
+    pub image: String,
+    pub proxy: Option<ProxyArgs>,
+    /// Size of each server's volume claim, as a Kubernetes quantity
+    pub storage_size: String,
+}
+
+#[derive(Debug)]
+pub struct ProxyArgs {
+    pub image: String,
+    pub port: u16,
+}
+
+pub fn export_args(
+    config: ExportConfig,
+    image: Option<String>,
+    proxy_image: Option<String>,
+    proxy_port: Option<u16>,
+    storage_size: Option<String>,
+) -> ExportArgs {
+    let proxy_port = proxy_port.or(config.proxy_port).unwrap_or(25577);
+
+    ExportArgs {
+        image: image
+            .or(config.image)
+            .unwrap_or_else(|| "eclipse-temurin:17-jre".into()),
+        proxy: proxy_image.or(config.proxy_image).map(|image| ProxyArgs {
+            image,
+            port: proxy_port,
+        }),
+        storage_size: storage_size
+            .or(config.storage_size)
+            .unwrap_or_else(|| "10Gi".into()),
+    }
+}
+
 pub struct WorldManagementArgs {
     pub world_diameter: Option<u32>,
     pub slice_width: Option<u32>,
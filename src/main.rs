@@ -34,22 +34,36 @@
 
 use std::path::PathBuf;
 
-use arg_types::{JarType, ServerMemory, ServerProperty};
+use arg_types::{Backend, JarType, ReadyCheck, ServerMemory, ServerProperty, ServerSelector};
 use clap::{IntoApp, Parser, ValueHint};
 use clap_complete::Shell;
 use color_eyre::Result;
+use regex::Regex;
 use tracing::{error, warn};
 
 mod arg_types;
+mod cmd_cache;
 mod cmd_combine_optimize;
+mod cmd_console;
+mod cmd_exec;
+mod cmd_export;
 mod cmd_init;
+mod cmd_logs;
 mod cmd_remove;
 mod cmd_reset_world;
 mod cmd_start_stop;
+mod cmd_status;
+mod cmd_supervise;
 mod cmd_sync;
+mod cmd_systemd;
 mod cmd_update_server;
+mod cmd_versions;
 mod config;
+mod process_backend;
+mod rcon;
 mod server_jar;
+mod server_list_ping;
+mod server_log;
 mod utils;
 
 #[derive(Debug, Clone, Parser)]
@@ -63,6 +77,10 @@ pub struct Args {
     #[clap(short = 'J', long, value_hint = ValueHint::Other)]
     jar_version: Option<String>,
 
+    /// Server .jar build number [default: latest build]
+    #[clap(short = 'B', long, value_hint = ValueHint::Other)]
+    jar_build: Option<String>,
+
     /// Number of servers to initialise [default: 2]
     #[clap(short = 'c', long, value_hint = ValueHint::Other)]
     server_count: Option<u8>,
@@ -79,8 +97,24 @@ pub struct Args {
     #[clap(short, long, value_hint = ValueHint::Other)]
     directory_template: Option<String>,
 
+    /// Path or URL of the server .jar to use with the "custom" jar type
+    #[clap(long, value_hint = ValueHint::AnyPath)]
+    jar_source: Option<String>,
+
+    /// Expected SHA-256 hash of the custom server .jar
+    #[clap(long, value_hint = ValueHint::Other)]
+    jar_checksum: Option<String>,
+
+    /// Fabric loader version [default: latest loader for the game version]
+    #[clap(long, value_hint = ValueHint::Other)]
+    fabric_loader_version: Option<String>,
+
+    /// Fabric installer version [default: latest installer]
+    #[clap(long, value_hint = ValueHint::Other)]
+    fabric_installer_version: Option<String>,
+
     /// Directories to sync to all instances [default: ["plugins"]]
-    /// Any directory named "plugins" will sync to each server's plugins directory
+    /// Any directory named "plugins" or "mods" will sync to each server's directory of the same name
     /// Other directories will sync to each server's root directory
     #[clap(long = "sync-dir", multiple_occurrences = true, multiple_values = false, value_hint = ValueHint::DirPath)]
     sync_dirs: Vec<PathBuf>,
@@ -89,10 +123,44 @@ pub struct Args {
     #[clap(short, long, value_hint = ValueHint::Other)]
     timeout_secs: Option<u8>,
 
+    /// Maximum number of servers to start, stop or restart at once [default: all servers]
+    #[clap(short = 'P', long, value_hint = ValueHint::Other)]
+    parallelism: Option<usize>,
+
+    /// Run servers in "tmux" sessions, as "native" detached processes, or with "systemd" units [default: "tmux"]
+    #[clap(long, value_hint = ValueHint::Other)]
+    backend: Option<Backend>,
+
+    /// Directory to cache downloaded server .jar files in [default: "~/.cache/provisioner"]
+    #[clap(long, value_hint = ValueHint::DirPath)]
+    cache_dir: Option<PathBuf>,
+
     #[clap(subcommand)]
     command: Command,
 }
 
+/// JVM settings for commands that start servers
+#[derive(Debug, Clone, clap::Args)]
+struct StartFlags {
+    /// Maximum amount of RAM to allocate to each server [default: "1G"]
+    #[clap(short = 'M', long, value_hint = ValueHint::Other)]
+    max_memory: Option<ServerMemory>,
+
+    /// Use Aikar's JVM flags [default: false]
+    #[clap(long)]
+    use_aikar_flags: Option<bool>,
+
+    /// Additional JVM args. Overrides Aikar's flags if set
+    #[clap(long, value_hint = ValueHint::Other)]
+    jvm_args: Option<String>,
+}
+
+impl StartFlags {
+    fn start_args(self, config: config::StartConfig) -> config::StartArgs {
+        config::start_args(config, self.max_memory, self.use_aikar_flags, self.jvm_args)
+    }
+}
+
 #[derive(Debug, Clone, Parser)]
 enum Command {
     #[clap(about = "Initialise and configure each server")]
@@ -129,17 +197,54 @@ enum Command {
             value_hint = ValueHint::Other
         )]
         server_properties: Vec<ServerProperty>,
+
+        /// Enable RCON, using a generated password shared by all servers [default: false]
+        #[clap(long)]
+        enable_rcon: Option<bool>,
+
+        /// RCON port to start counting at [default: 10000 above --start-port]
+        #[clap(long, value_hint = ValueHint::Other)]
+        rcon_start_port: Option<u16>,
     },
 
     #[clap(about = "Sync specified directories to all servers")]
     Sync {
-        /// Clears plugins directory before syncing [default: false]
+        /// Clears plugins and mods directories before syncing [default: false]
         #[clap(short = 'c', long)]
         clear_plugins: bool,
     },
 
-    #[clap(about = "Update server .jar to the latest build for a given version")]
-    UpdateServer,
+    #[clap(about = "Update server .jar to the latest (or pinned) build for a given version")]
+    UpdateServer {
+        /// Only check for updates, exiting with status 2 if any server is out of date
+        #[clap(long)]
+        check: bool,
+
+        /// Stop, update and start running servers one at a time instead of refusing to update them
+        #[clap(long, conflicts_with = "check")]
+        rolling: bool,
+
+        /// Seconds to wait for each server to pass its ready check during a rolling update [default: 120]
+        #[clap(long, value_hint = ValueHint::Other)]
+        startup_timeout_secs: Option<u16>,
+
+        #[clap(flatten)]
+        start_flags: StartFlags,
+    },
+
+    #[clap(about = "Restore the previously installed server .jar on all servers")]
+    RollbackServer {
+        /// Stop, roll back and start running servers one at a time instead of refusing to roll them back
+        #[clap(long)]
+        rolling: bool,
+
+        /// Seconds to wait for each server to pass its ready check during a rolling rollback [default: 120]
+        #[clap(long, value_hint = ValueHint::Other)]
+        startup_timeout_secs: Option<u16>,
+
+        #[clap(flatten)]
+        start_flags: StartFlags,
+    },
 
     #[clap(about = "Resets each server's world")]
     ResetWorld,
@@ -149,17 +254,25 @@ enum Command {
 
     #[clap(about = "Start all servers in the background")]
     Start {
-        /// Maximum amount of RAM to allocate to each server [default: "1G"]
-        #[clap(short = 'M', long, value_hint = ValueHint::Other)]
-        max_memory: Option<ServerMemory>,
+        #[clap(flatten)]
+        start_flags: StartFlags,
 
-        /// Use Aikar's JVM flags [default: false]
+        /// Wait until each server passes its ready check
         #[clap(long)]
-        use_aikar_flags: Option<bool>,
+        wait: bool,
+
+        /// Seconds to wait for each server to pass its ready check [default: 120]
+        #[clap(long, value_hint = ValueHint::Other)]
+        startup_timeout_secs: Option<u16>,
+
+        /// How to tell that a server is ready: "port", "ping" or "log" [default: "ping"]
+        #[clap(long, value_hint = ValueHint::Other)]
+        ready_check: Option<ReadyCheck>,
 
-        /// Additional JVM args. Overides Aikar's flags if set
+        /// Regex matched against new lines in logs/latest.log with --ready-check log
+        /// [default: "Done \\([0-9.,]+s\\)! For help"]
         #[clap(long, value_hint = ValueHint::Other)]
-        jvm_args: Option<String>,
+        ready_log_pattern: Option<String>,
     },
 
     #[clap(about = "Stop each background server process")]
@@ -167,17 +280,122 @@ enum Command {
 
     #[clap(about = "Restart all servers")]
     Restart {
-        /// Maximum amount of RAM to allocate to each server [default: "1G"]
-        #[clap(short = 'M', long, value_hint = ValueHint::Other)]
-        max_memory: Option<ServerMemory>,
+        #[clap(flatten)]
+        start_flags: StartFlags,
 
-        /// Use Aikar's JVM flags [default: false]
+        /// Wait until each server passes its ready check
         #[clap(long)]
-        use_aikar_flags: Option<bool>,
+        wait: bool,
 
-        /// Additional JVM args. Overides Aikar's flags if set
+        /// Restart servers a batch at a time, waiting until each batch is ready before the next
+        #[clap(long)]
+        rolling: bool,
+
+        /// Number of servers to restart at once with --rolling [default: 1]
+        #[clap(long, requires = "rolling", value_hint = ValueHint::Other)]
+        batch_size: Option<usize>,
+
+        /// Seconds to wait for each server to pass its ready check [default: 120]
         #[clap(long, value_hint = ValueHint::Other)]
-        jvm_args: Option<String>,
+        startup_timeout_secs: Option<u16>,
+
+        /// How to tell that a server is ready: "port", "ping" or "log" [default: "ping"]
+        #[clap(long, value_hint = ValueHint::Other)]
+        ready_check: Option<ReadyCheck>,
+
+        /// Regex matched against new lines in logs/latest.log with --ready-check log
+        /// [default: "Done \\([0-9.,]+s\\)! For help"]
+        #[clap(long, value_hint = ValueHint::Other)]
+        ready_log_pattern: Option<String>,
+    },
+
+    #[clap(about = "Watch running servers and restart any that crash")]
+    Supervise {
+        #[clap(flatten)]
+        start_flags: StartFlags,
+
+        /// Crashes allowed within --crash-window-secs before a server is left stopped [default: 5]
+        #[clap(long, value_hint = ValueHint::Other)]
+        max_crashes: Option<usize>,
+
+        /// Seconds that crashes count towards --max-crashes for [default: 600]
+        #[clap(long, value_hint = ValueHint::Other)]
+        crash_window_secs: Option<u64>,
+
+        /// Seconds to wait before restarting after a first crash, doubling with each crash [default: 5]
+        #[clap(long, value_hint = ValueHint::Other)]
+        backoff_secs: Option<u64>,
+
+        /// Longest wait before restarting a crashed server [default: 300]
+        #[clap(long, value_hint = ValueHint::Other)]
+        max_backoff_secs: Option<u64>,
+    },
+
+    #[clap(
+        about = "Run a console command on each server over RCON",
+        setting = clap::AppSettings::TrailingVarArg
+    )]
+    Exec {
+        /// Only run on these servers, by index, port or directory name [default: all servers]
+        #[clap(
+            short,
+            long = "server",
+            multiple_occurrences = true,
+            multiple_values = false,
+            value_hint = ValueHint::Other
+        )]
+        servers: Vec<ServerSelector>,
+
+        /// Console command, without a leading slash
+        #[clap(
+            required = true,
+            multiple_values = true,
+            value_hint = ValueHint::Other
+        )]
+        command: Vec<String>,
+    },
+
+    #[clap(about = "Attach to a server's console")]
+    Console {
+        /// Server to attach to, by index, port or directory name
+        #[clap(value_hint = ValueHint::Other)]
+        server: ServerSelector,
+    },
+
+    #[clap(about = "Print the logs of every server, interleaved and labelled")]
+    Logs {
+        /// Keep printing new lines as they are logged
+        #[clap(short, long)]
+        follow: bool,
+
+        /// Number of existing lines to print from each server before following
+        #[clap(short = 'n', long, default_value = "10", value_hint = ValueHint::Other)]
+        lines: usize,
+
+        /// Only show these servers, by index, port or directory name [default: all servers]
+        #[clap(
+            short,
+            long = "server",
+            multiple_occurrences = true,
+            multiple_values = false,
+            value_hint = ValueHint::Other
+        )]
+        servers: Vec<ServerSelector>,
+
+        /// Only show lines matching this regex
+        #[clap(long, value_hint = ValueHint::Other)]
+        grep: Option<Regex>,
+
+        /// Include the gzipped logs from previous runs
+        #[clap(short, long)]
+        archived: bool,
+    },
+
+    #[clap(about = "Show whether each server is running and reachable")]
+    Status {
+        /// Print as JSON
+        #[clap(long)]
+        json: bool,
     },
 
     #[clap(about = "Merge all world region files into a single folder")]
@@ -216,6 +434,35 @@ enum Command {
         combined_directory: Option<PathBuf>,
     },
 
+    #[clap(about = "List available game versions and recent builds")]
+    Versions {
+        /// Number of recent builds to list [default: 10]
+        #[clap(short = 'n', long, value_hint = ValueHint::Other)]
+        limit: Option<usize>,
+
+        /// Print as JSON
+        #[clap(long)]
+        json: bool,
+    },
+
+    #[clap(about = "Export the cluster for other ways of running it")]
+    Export {
+        #[clap(subcommand)]
+        command: ExportCommand,
+    },
+
+    #[clap(about = "Manage systemd user units for each server")]
+    Systemd {
+        #[clap(subcommand)]
+        command: SystemdCommand,
+    },
+
+    #[clap(about = "Manage the server .jar download cache")]
+    Cache {
+        #[clap(subcommand)]
+        command: CacheCommand,
+    },
+
     #[clap(about = "Generate shell completions")]
     Completions {
         /// CLI shell type
@@ -224,6 +471,115 @@ enum Command {
     },
 }
 
+impl Command {
+    /// Whether the command acts on the server directories, so has nothing to do without servers
+    fn uses_servers(&self) -> bool {
+        // Cache management and version listing don't depend on any server config
+        !matches!(self, Command::Cache { .. } | Command::Versions { .. })
+    }
+
+    /// Whether the command needs a game version to pick a server .jar
+    fn needs_jar_version(&self) -> bool {
+        // These only talk to existing servers, or list the versions to pick from
+        self.uses_servers()
+            && !matches!(
+                self,
+                Command::Status { .. }
+                    | Command::Exec { .. }
+                    | Command::Console { .. }
+                    | Command::Logs { .. }
+            )
+    }
+}
+
+#[derive(Debug, Clone, Parser)]
+enum ExportCommand {
+    #[clap(about = "Write a Docker Compose file with a service for each server")]
+    Compose {
+        /// File to write
+        #[clap(short, long, default_value = "docker-compose.yml", value_hint = ValueHint::FilePath)]
+        output: PathBuf,
+
+        /// Container image with Java to run each server in [default: "eclipse-temurin:17-jre"]
+        #[clap(long, value_hint = ValueHint::Other)]
+        image: Option<String>,
+
+        /// Container image for a proxy service in front of the servers [default: no proxy]
+        #[clap(long, value_hint = ValueHint::Other)]
+        proxy_image: Option<String>,
+
+        /// Host port for the proxy [default: 25577]
+        #[clap(long, value_hint = ValueHint::Other)]
+        proxy_port: Option<u16>,
+
+        #[clap(flatten)]
+        start_flags: StartFlags,
+    },
+
+    #[clap(
+        about = "Write Kubernetes manifests with a Deployment, volume and Service for each server",
+        alias = "k8s"
+    )]
+    Kubernetes {
+        /// File to write
+        #[clap(short, long, default_value = "kubernetes.yml", value_hint = ValueHint::FilePath)]
+        output: PathBuf,
+
+        /// Container image with Java to run each server in [default: "eclipse-temurin:17-jre"]
+        #[clap(long, value_hint = ValueHint::Other)]
+        image: Option<String>,
+
+        /// Storage to request for each server's volume [default: "10Gi"]
+        #[clap(long, value_hint = ValueHint::Other)]
+        storage_size: Option<String>,
+
+        #[clap(flatten)]
+        start_flags: StartFlags,
+    },
+}
+
+#[derive(Debug, Clone, Parser)]
+enum SystemdCommand {
+    #[clap(about = "Write a unit for each server to a directory")]
+    Generate {
+        /// Directory to write units to
+        #[clap(short, long, default_value = "systemd", value_hint = ValueHint::DirPath)]
+        output_dir: PathBuf,
+
+        #[clap(flatten)]
+        start_flags: StartFlags,
+    },
+
+    #[clap(about = "Install a user unit for each server and reload systemd")]
+    Install {
+        /// Start each server when the user's systemd instance starts
+        #[clap(long)]
+        enable: bool,
+
+        #[clap(flatten)]
+        start_flags: StartFlags,
+    },
+
+    #[clap(about = "Stop, disable and remove each server's user unit")]
+    Uninstall,
+}
+
+#[derive(Debug, Clone, Parser)]
+enum CacheCommand {
+    #[clap(about = "List cached server .jar files")]
+    List,
+
+    #[clap(about = "Remove all but the newest builds of each cached version")]
+    Prune {
+        /// Number of builds to keep for each version [default: 1]
+        #[clap(short, long, value_hint = ValueHint::Other)]
+        keep: Option<usize>,
+    },
+
+    #[clap(about = "Remove all cached server .jar files")]
+    Clear,
+}
+
 fn main() -> Result<()> {
     color_eyre::install()?;
     tracing_subscriber::fmt()
@@ -234,23 +590,52 @@ fn main() -> Result<()> {
     let config = config::read_config()?;
     let args = Args::parse();
     let global_args = config::global_args(config.global.unwrap_or_default(), args.clone());
+    server_jar::http::configure(config::http_options(config.http.unwrap_or_default()))?;
 
-    if global_args.server_count == 0 {
+    if global_args.server_count == 0 && args.command.uses_servers() {
         warn!("no action taken as --server-count was set to 0");
         return Ok(());
     }
 
-    if global_args.jar_version.is_empty() {
+    if global_args.jar_version.is_empty() && args.command.needs_jar_version() {
         error!("you must specify a server .jar version");
         std::process::exit(1);
     }
 
     match args.command {
+        Command::Cache { command } => match command {
+            CacheCommand::List => cmd_cache::list(global_args)?,
+            CacheCommand::Prune { keep } => cmd_cache::prune(global_args, keep.unwrap_or(1))?,
+            CacheCommand::Clear => cmd_cache::clear(global_args)?,
+        },
+
+        Command::Versions { limit, json } => {
+            cmd_versions::versions(global_args, limit.unwrap_or(10), json)?
+        }
+
+        Command::Status { json } => cmd_status::status(global_args, json)?,
+
+        Command::Exec { servers, command } => {
+            cmd_exec::exec(global_args, &servers, &command.join(" "))?
+        }
+
+        Command::Console { server } => cmd_console::console(global_args, &server)?,
+
+        Command::Logs {
+            follow,
+            lines,
+            servers,
+            grep,
+            archived,
+        } => cmd_logs::logs(global_args, &servers, follow, lines, archived, grep)?,
+
         Command::Init {
             level_seed,
             ops,
             white_list,
             server_properties,
+            enable_rcon,
+            rcon_start_port,
         } => {
             let init_args = config::init_args(
                 config.init.unwrap_or_default(),
@@ -258,6 +643,8 @@ fn main() -> Result<()> {
                 ops,
                 white_list,
                 server_properties,
+                enable_rcon,
+                rcon_start_port,
             );
 
             if let Err(error) = init_args {
@@ -276,44 +663,220 @@ fn main() -> Result<()> {
 
         Command::Sync { clear_plugins } => cmd_sync::sync(global_args, clear_plugins)?,
 
-        Command::UpdateServer => cmd_update_server::update_server(global_args)?,
+        Command::UpdateServer {
+            check,
+            rolling,
+            startup_timeout_secs,
+            start_flags,
+        } => {
+            if check {
+                cmd_update_server::check(global_args)?
+            } else {
+                let start_config = config.start.unwrap_or_default();
+                let readiness = config::readiness(&start_config, None, None, startup_timeout_secs)?;
+                let start_args = start_flags.start_args(start_config);
+
+                let rolling = rolling.then(|| cmd_update_server::RollingArgs {
+                    start_args,
+                    readiness,
+                });
+
+                cmd_update_server::update_server(global_args, rolling)?
+            }
+        }
+
+        Command::RollbackServer {
+            rolling,
+            startup_timeout_secs,
+            start_flags,
+        } => {
+            let start_config = config.start.unwrap_or_default();
+            let readiness = config::readiness(&start_config, None, None, startup_timeout_secs)?;
+            let start_args = start_flags.start_args(start_config);
+
+            let rolling = rolling.then(|| cmd_update_server::RollingArgs {
+                start_args,
+                readiness,
+            });
+
+            cmd_update_server::rollback_server(global_args, rolling)?
+        }
 
         Command::ResetWorld => cmd_reset_world::reset_world(global_args)?,
 
         Command::Remove => cmd_remove::remove(global_args)?,
 
         Command::Start {
-            max_memory,
-            use_aikar_flags,
-            jvm_args,
+            start_flags,
+            wait,
+            startup_timeout_secs,
+            ready_check,
+            ready_log_pattern,
         } => {
-            let start_args = config::start_args(
-                config.start.unwrap_or_default(),
-                max_memory,
-                use_aikar_flags,
-                jvm_args,
-            );
-
-            cmd_start_stop::start(global_args, start_args)?
+            let start_config = config.start.unwrap_or_default();
+            let readiness = config::readiness(
+                &start_config,
+                ready_check,
+                ready_log_pattern,
+                startup_timeout_secs,
+            )?;
+            let start_args = start_flags.start_args(start_config);
+
+            cmd_start_stop::start(global_args, start_args, wait.then_some(readiness))?
         }
 
         Command::Stop => cmd_start_stop::stop(global_args)?,
 
         Command::Restart {
-            max_memory,
-            use_aikar_flags,
-            jvm_args,
+            start_flags,
+            wait,
+            rolling,
+            batch_size,
+            startup_timeout_secs,
+            ready_check,
+            ready_log_pattern,
         } => {
-            let start_args = config::start_args(
-                config.start.unwrap_or_default(),
-                max_memory,
-                use_aikar_flags,
-                jvm_args,
+            let start_config = config.start.unwrap_or_default();
+            let readiness = config::readiness(
+                &start_config,
+                ready_check,
+                ready_log_pattern,
+                startup_timeout_secs,
+            )?;
+            let start_args = start_flags.start_args(start_config);
+
+            // Rolling restarts always wait, otherwise every batch would restart at once
+            let batch_size = rolling.then(|| batch_size.unwrap_or(1));
+            let wait = (wait || rolling).then_some(readiness);
+
+            cmd_start_stop::restart(global_args, start_args, wait, batch_size)?
+        }
+
+        Command::Supervise {
+            start_flags,
+            max_crashes,
+            crash_window_secs,
+            backoff_secs,
+            max_backoff_secs,
+        } => {
+            let start_args = start_flags.start_args(config.start.unwrap_or_default());
+
+            let supervise_args = config::supervise_args(
+                config.supervise.unwrap_or_default(),
+                max_crashes,
+                crash_window_secs,
+                backoff_secs,
+                max_backoff_secs,
             );
 
-            cmd_start_stop::restart(global_args, start_args)?
+            cmd_supervise::supervise(global_args, start_args, supervise_args)?
         }
 
+        Command::Export { command } => match command {
+            ExportCommand::Compose {
+                output,
+                image,
+                proxy_image,
+                proxy_port,
+                start_flags,
+            } => {
+                let start_args = start_flags.start_args(config.start.unwrap_or_default());
+
+                let export_args = config::export_args(
+                    config.export.unwrap_or_default(),
+                    image,
+                    proxy_image,
+                    proxy_port,
+                    None,
+                );
+
+                cmd_export::compose(global_args, start_args, export_args, &output)?
+            }
+
+            ExportCommand::Kubernetes {
+                output,
+                image,
+                storage_size,
+                start_flags,
+            } => {
+                // Only the config is used, so it stays the one source of truth for the cluster
+                let init_args = config::init_args(
+                    config.init.unwrap_or_default(),
+                    None,
+                    vec![],
+                    vec![],
+                    vec![],
+                    None,
+                    None,
+                );
+
+                let init_args = match init_args {
+                    Ok(init_args) => init_args,
+                    Err(error) => {
+                        error!("{}", error);
+                        std::process::exit(1);
+                    }
+                };
+
+                if init_args.level_seed.is_empty() {
+                    error!(
+                        "you must set init.level_seed, else all servers will have different seeds"
+                    );
+                    std::process::exit(1);
+                }
+
+                let start_args = start_flags.start_args(config.start.unwrap_or_default());
+
+                let export_args = config::export_args(
+                    config.export.unwrap_or_default(),
+                    image,
+                    None,
+                    None,
+                    storage_size,
+                );
+
+                cmd_export::kubernetes(global_args, init_args, start_args, export_args, &output)?
+            }
+        },
+
+        Command::Systemd { command } => match command {
+            SystemdCommand::Generate {
+                output_dir,
+                start_flags,
+            } => {
+                let start_args = start_flags.start_args(config.start.unwrap_or_default());
+
+                let supervise_args = config::supervise_args(
+                    config.supervise.unwrap_or_default(),
+                    None,
+                    None,
+                    None,
+                    None,
+                );
+
+                cmd_systemd::generate(global_args, start_args, supervise_args, &output_dir)?
+            }
+
+            SystemdCommand::Install {
+                enable,
+                start_flags,
+            } => {
+                let start_args = start_flags.start_args(config.start.unwrap_or_default());
+
+                let supervise_args = config::supervise_args(
+                    config.supervise.unwrap_or_default(),
+                    None,
+                    None,
+                    None,
+                    None,
+                );
+
+                cmd_systemd::install(global_args, start_args, supervise_args, enable)?
+            }
+
+            SystemdCommand::Uninstall => cmd_systemd::uninstall(global_args)?,
+        },
+
         Command::Combine {
             world_diameter,
             slice_width,
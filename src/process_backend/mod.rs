@@ -0,0 +1,63 @@
+use std::fmt::Display;
+use std::time::Duration;
+
+mod native;
+mod systemd;
+mod tmux;
+
+pub use native::NativeBackend;
+pub use systemd::SystemdBackend;
+pub use tmux::TmuxBackend;
+
+/// Runs each server in the background, inside a session named after its directory
+///
+/// A session outlives the server process running in it, so a crashed server still has a
+/// session until it is stopped
+pub trait ProcessBackend {
+    /// Whether the server has a session, even if nothing is running in it
+    fn session_exists(&self, name: &str) -> bool;
+
+    /// Creates an empty session for the server
+    fn create_session(&self, name: &str) -> bool;
+
+    /// Runs a shell command from the server's directory in its session
+    fn run(&self, name: &str, command: &str) -> bool;
+
+    /// Asks the server to shut down without going through RCON
+    ///
+    /// Returns how it was asked, or `None` if it couldn't be signalled
+    fn interrupt(&self, name: &str) -> Option<StopMethod>;
+
+    /// Waits for the last command passed to `run` to exit, returns `false` on timeout
+    fn wait_for_exit(&self, name: &str, timeout: Duration) -> bool;
+
+    /// Ends the session, killing anything still running in it
+    fn kill_session(&self, name: &str) -> bool;
+
+    /// PID of the server's Java process, `None` if it isn't running
+    fn java_pid(&self, name: &str) -> Option<u32>;
+}
+
+/// How a server was asked to shut down
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum StopMethod {
+    Rcon,
+    /// Ctrl-C sent to the server's tmux pane
+    Interrupt,
+    /// `stop` written to the server's console input
+    Console,
+    Terminate,
+    Systemd,
+}
+
+impl Display for StopMethod {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            Self::Rcon => write!(f, "rcon"),
+            Self::Interrupt => write!(f, "tmux Ctrl-C"),
+            Self::Console => write!(f, "console stop"),
+            Self::Terminate => write!(f, "SIGTERM"),
+            Self::Systemd => write!(f, "systemctl stop"),
+        }
+    }
+}
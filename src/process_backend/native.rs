@@ -0,0 +1,211 @@
+use std::fs::{self, OpenOptions};
+use std::io::{self, Write};
+use std::os::unix::fs::FileTypeExt;
+use std::os::unix::process::CommandExt;
+use std::path::{Path, PathBuf};
+use std::process::{Command, Stdio};
+use std::thread;
+use std::time::{Duration, Instant, SystemTime, UNIX_EPOCH};
+
+use super::{ProcessBackend, StopMethod};
+use crate::utils;
+
+/// Holds the process group ID of the running server, and marks the session as existing
+const PID_FILE: &str = ".pid";
+
+/// Named pipe the server reads console commands from
+const STDIN_FIFO: &str = ".stdin";
+
+/// Server stdout and stderr
+const CONSOLE_LOG: &str = "console.log";
+
+/// Runs each server as a detached process group, for hosts without tmux
+///
+/// The server's console output is appended to `console.log`, and console commands can be
+/// written to the `.stdin` named pipe in its directory
+#[derive(Debug)]
+pub struct NativeBackend;
+
+impl NativeBackend {
+    /// Path of the server's PID file, `None` if it has no session
+    ///
+    /// A PID file written before the host last booted is removed, as its process group is gone
+    /// and the ID may have been reused by something else since
+    fn pid_file(name: &str) -> Option<PathBuf> {
+        let pid_file = Path::new(name).join(PID_FILE);
+        let written = fs::metadata(&pid_file)
+            .and_then(|metadata| metadata.modified())
+            .ok()?;
+
+        if boot_time().is_some_and(|boot_time| written < boot_time) {
+            let _ = fs::remove_file(&pid_file);
+            return None;
+        }
+
+        Some(pid_file)
+    }
+
+    fn process_group(name: &str) -> Option<u32> {
+        let pid = fs::read_to_string(Self::pid_file(name)?).ok()?;
+        pid.trim().parse().ok()
+    }
+
+    /// Sends a signal to every process in the group
+    fn signal(process_group: u32, signal: &str) -> bool {
+        // A negative PID targets the whole process group
+        let target = format!("-{}", process_group);
+
+        Command::new("kill")
+            .args([signal, "--", &target])
+            .stdout(Stdio::null())
+            .stderr(Stdio::null())
+            .status()
+            .map(|status| status.success())
+            .unwrap_or(false)
+    }
+
+    fn is_running(name: &str) -> bool {
+        Self::process_group(name).is_some_and(|process_group| Self::signal(process_group, "-0"))
+    }
+
+    /// Writes a line to the server's console input
+    pub fn send_console(name: &str, line: &str) -> io::Result<()> {
+        // Opening a named pipe read-write never blocks, even if the server isn't reading it
+        let mut stdin = OpenOptions::new()
+            .read(true)
+            .write(true)
+            .open(Path::new(name).join(STDIN_FIFO))?;
+
+        stdin.write_all(format!("{}\n", line).as_bytes())
+    }
+}
+
+impl ProcessBackend for NativeBackend {
+    fn session_exists(&self, name: &str) -> bool {
+        Self::pid_file(name).is_some()
+    }
+
+    fn create_session(&self, name: &str) -> bool {
+        let fifo = Path::new(name).join(STDIN_FIFO);
+        if fifo
+            .symlink_metadata()
+            .is_ok_and(|metadata| metadata.file_type().is_fifo())
+        {
+            return true;
+        }
+
+        let _ = fs::remove_file(&fifo);
+        Command::new("mkfifo")
+            .arg(&fifo)
+            .status()
+            .map(|status| status.success())
+            .unwrap_or(false)
+    }
+
+    fn run(&self, name: &str, command: &str) -> bool {
+        let directory = Path::new(name);
+        if !self.create_session(name) {
+            return false;
+        }
+
+        // Only one server runs in a session, so replace anything left from a timed out stop
+        if Self::is_running(name) {
+            Self::signal(Self::process_group(name).unwrap(), "-KILL");
+        }
+
+        let console_log = OpenOptions::new()
+            .create(true)
+            .append(true)
+            .open(directory.join(CONSOLE_LOG));
+
+        let (stdout, stderr) = match console_log.and_then(|log| Ok((log.try_clone()?, log))) {
+            Ok(console_log) => console_log,
+            Err(_) => return false,
+        };
+
+        // Backgrounding the command lets this shell exit straight away, leaving the server to be
+        // adopted by init. Background commands read from /dev/null unless redirected, and the
+        // pipe is opened read-write so the server never sees the end of its input.
+        let script = format!("( {} ) <> {} &", command, STDIN_FIFO);
+        let child = Command::new("sh")
+            .arg("-c")
+            .arg(script)
+            .current_dir(directory)
+            .stdin(Stdio::null())
+            .stdout(stdout)
+            .stderr(stderr)
+            .process_group(0)
+            .spawn();
+
+        let mut child = match child {
+            Ok(child) => child,
+            Err(_) => return false,
+        };
+
+        // The shell leads the process group, which the server stays in after the shell exits
+        let process_group = child.id();
+        if !child.wait().is_ok_and(|status| status.success()) {
+            return false;
+        }
+
+        fs::write(directory.join(PID_FILE), process_group.to_string()).is_ok()
+    }
+
+    fn interrupt(&self, name: &str) -> Option<StopMethod> {
+        let process_group = Self::process_group(name)?;
+        if Self::send_console(name, "stop").is_ok() {
+            return Some(StopMethod::Console);
+        }
+
+        Self::signal(process_group, "-TERM").then_some(StopMethod::Terminate)
+    }
+
+    fn wait_for_exit(&self, name: &str, timeout: Duration) -> bool {
+        let deadline = Instant::now() + timeout;
+        while Instant::now() < deadline {
+            if !Self::is_running(name) {
+                return true;
+            }
+
+            thread::sleep(Duration::from_millis(250));
+        }
+
+        false
+    }
+
+    fn kill_session(&self, name: &str) -> bool {
+        if Self::is_running(name) && !Self::signal(Self::process_group(name).unwrap(), "-KILL") {
+            return false;
+        }
+
+        match fs::remove_file(Path::new(name).join(PID_FILE)) {
+            Ok(()) => true,
+            Err(error) => error.kind() == io::ErrorKind::NotFound,
+        }
+    }
+
+    fn java_pid(&self, name: &str) -> Option<u32> {
+        let process_group = Self::process_group(name)?.to_string();
+        let pids = utils::command_output(Command::new("pgrep").args([
+            "-g",
+            &process_group,
+            "-x",
+            "java",
+        ]))?;
+
+        pids.lines().next()?.parse().ok()
+    }
+}
+
+/// When the host last booted, from `/proc/stat`
+fn boot_time() -> Option<SystemTime> {
+    let stat = fs::read_to_string("/proc/stat").ok()?;
+    let secs = stat
+        .lines()
+        .find_map(|line| line.strip_prefix("btime "))?
+        .trim()
+        .parse()
+        .ok()?;
+
+    Some(UNIX_EPOCH + Duration::from_secs(secs))
+}
@@ -0,0 +1,99 @@
+use std::process::{Command, Stdio};
+use std::thread;
+use std::time::{Duration, Instant};
+
+use tracing::error;
+
+use super::{ProcessBackend, StopMethod};
+use crate::utils;
+
+/// Delegates to the units written by `provisioner systemd install`, managed with
+/// `systemctl --user`
+///
+/// Units run the server command they were generated with, so the command passed to `run` is
+/// ignored, and systemd restarts crashed servers itself
+#[derive(Debug)]
+pub struct SystemdBackend;
+
+impl SystemdBackend {
+    pub fn unit_name(name: &str) -> String {
+        format!("provisioner-{}.service", name)
+    }
+
+    pub fn systemctl(args: &[&str]) -> bool {
+        Command::new("systemctl")
+            .arg("--user")
+            .args(args)
+            .stdout(Stdio::null())
+            .status()
+            .map(|status| status.success())
+            .unwrap_or(false)
+    }
+
+    fn property(name: &str, property: &str) -> Option<String> {
+        let unit = Self::unit_name(name);
+        utils::command_output(
+            Command::new("systemctl").args(["--user", "show", "-p", property, "--value", &unit]),
+        )
+    }
+
+    /// Whether the unit is running, or starting or stopping
+    fn is_active(name: &str) -> bool {
+        !matches!(
+            Self::property(name, "ActiveState").as_deref(),
+            None | Some("inactive" | "failed")
+        )
+    }
+}
+
+impl ProcessBackend for SystemdBackend {
+    fn session_exists(&self, name: &str) -> bool {
+        Self::is_active(name)
+    }
+
+    fn create_session(&self, name: &str) -> bool {
+        if Self::property(name, "LoadState").as_deref() != Some("loaded") {
+            error!(
+                "{} is not installed, run `provisioner systemd install` first",
+                Self::unit_name(name)
+            );
+            return false;
+        }
+
+        true
+    }
+
+    fn run(&self, name: &str, _command: &str) -> bool {
+        Self::systemctl(&["start", &Self::unit_name(name)])
+    }
+
+    fn interrupt(&self, name: &str) -> Option<StopMethod> {
+        Self::systemctl(&["stop", "--no-block", &Self::unit_name(name)])
+            .then_some(StopMethod::Systemd)
+    }
+
+    fn wait_for_exit(&self, name: &str, timeout: Duration) -> bool {
+        let deadline = Instant::now() + timeout;
+        while Instant::now() < deadline {
+            if !Self::is_active(name) {
+                return true;
+            }
+
+            thread::sleep(Duration::from_millis(250));
+        }
+
+        false
+    }
+
+    fn kill_session(&self, name: &str) -> bool {
+        // Stopping kills the server itself once the unit's stop timeout is reached
+        Self::systemctl(&["stop", &Self::unit_name(name)])
+    }
+
+    fn java_pid(&self, name: &str) -> Option<u32> {
+        match Self::property(name, "MainPID")?.parse() {
+            Ok(0) | Err(_) => None,
+            Ok(pid) => Some(pid),
+        }
+    }
+}
@@ -0,0 +1,110 @@
+use std::process::{Command, Stdio};
+use std::thread;
+use std::time::{Duration, Instant};
+
+use cmd_lib::run_cmd;
+
+use super::{ProcessBackend, StopMethod};
+use crate::utils;
+
+/// Runs each server in a tmux session, which can be attached to for console access
+#[derive(Debug)]
+pub struct TmuxBackend;
+
+impl TmuxBackend {
+    /// PID of the shell running in the server's tmux pane
+    fn pane_pid(name: &str) -> Option<u32> {
+        let target = format!("={}:", name);
+        let pid = utils::command_output(Command::new("tmux").args([
+            "display-message",
+            "-p",
+            "-t",
+            &target,
+            "#{pane_pid}",
+        ]))?;
+
+        pid.parse().ok()
+    }
+
+    /// Whether the pane's shell is still running a command, `false` once the session is gone
+    fn is_busy(name: &str) -> bool {
+        let shell_pid = match Self::pane_pid(name) {
+            Some(pid) => pid.to_string(),
+            None => return false,
+        };
+
+        utils::command_output(Command::new("pgrep").args(["-P", &shell_pid])).is_some()
+    }
+
+    /// Attaches the terminal to the server's session, or switches to it from inside tmux
+    pub fn attach(name: &str) -> bool {
+        let target = format!("={}", name);
+        let subcommand = match std::env::var_os("TMUX") {
+            Some(_) => "switch-client",
+            None => "attach-session",
+        };
+
+        Command::new("tmux")
+            .args([subcommand, "-t", &target])
+            .status()
+            .map(|status| status.success())
+            .unwrap_or(false)
+    }
+}
+
+impl ProcessBackend for TmuxBackend {
+    fn session_exists(&self, name: &str) -> bool {
+        // Prefix with `=` so that tmux only matches the exact session name
+        let target = format!("={}", name);
+
+        Command::new("tmux")
+            .args(["has-session", "-t", &target])
+            .stdout(Stdio::null())
+            .stderr(Stdio::null())
+            .status()
+            .map(|status| status.success())
+            .unwrap_or(false)
+    }
+
+    fn create_session(&self, name: &str) -> bool {
+        let cd = format!("cd ./{}", name);
+
+        run_cmd!(tmux new -d  -s $name).is_ok() && run_cmd!(tmux send -t $name $cd ENTER).is_ok()
+    }
+
+    fn run(&self, name: &str, command: &str) -> bool {
+        run_cmd!(tmux send -t $name $command ENTER).is_ok()
+    }
+
+    fn interrupt(&self, name: &str) -> Option<StopMethod> {
+        run_cmd!(tmux send -t $name C-c)
+            .is_ok()
+            .then_some(StopMethod::Interrupt)
+    }
+
+    fn wait_for_exit(&self, name: &str, timeout: Duration) -> bool {
+        // Polled rather than waiting on a tmux channel, which stays signalled between runs
+        let deadline = Instant::now() + timeout;
+        while Instant::now() < deadline {
+            if !Self::is_busy(name) {
+                return true;
+            }
+
+            thread::sleep(Duration::from_millis(250));
+        }
+
+        false
+    }
+
+    fn kill_session(&self, name: &str) -> bool {
+        run_cmd!(tmux kill-session -t $name).is_ok()
+    }
+
+    fn java_pid(&self, name: &str) -> Option<u32> {
+        let shell_pid = Self::pane_pid(name)?.to_string();
+        let pids =
+            utils::command_output(Command::new("pgrep").args(["-P", &shell_pid, "-x", "java"]))?;
+
+        pids.lines().next()?.parse().ok()
+    }
+}
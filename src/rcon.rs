@@ -0,0 +1,254 @@
+use std::io::{self, Read, Write};
+use std::net::{Ipv4Addr, SocketAddr, TcpStream};
+use std::path::Path;
+use std::time::Duration;
+
+use color_eyre::Result;
+use thiserror::Error;
+
+use crate::utils;
+
+const PACKET_RESPONSE: i32 = 0;
+const PACKET_COMMAND: i32 = 2;
+const PACKET_LOGIN: i32 = 3;
+
+/// Largest command the server will accept
+const MAX_COMMAND_LEN: usize = 1446;
+
+/// Largest packet the server will send, see https://wiki.vg/RCON
+const MAX_PACKET_LEN: i32 = 4096 + 10;
+
+#[derive(Debug, Error)]
+pub enum RconError {
+    #[error(transparent)]
+    Io(#[from] io::Error),
+
+    #[error("incorrect rcon password")]
+    AuthFailed,
+
+    #[error("command is longer than {} bytes", MAX_COMMAND_LEN)]
+    CommandTooLong,
+
+    #[error("invalid rcon packet length: {0}")]
+    InvalidPacket(i32),
+
+    #[error("invalid rcon.port in server.properties: {0}")]
+    InvalidPort(String),
+}
+
+/// RCON settings read from a server's `server.properties`
+#[derive(Debug, Clone)]
+pub struct RconSettings {
+    pub port: u16,
+    pub password: String,
+}
+
+impl RconSettings {
+    /// Returns `None` if RCON isn't enabled for the server
+    pub fn read(directory: &Path) -> Result<Option<Self>> {
+        let properties = utils::read_server_properties(directory)?;
+        if properties.get("enable-rcon").map(String::as_str) != Some("true") {
+            return Ok(None);
+        }
+
+        let password = match properties.get("rcon.password") {
+            Some(password) if !password.is_empty() => password.clone(),
+            _ => return Ok(None),
+        };
+
+        let port = match properties.get("rcon.port") {
+            Some(port) => port
+                .parse()
+                .map_err(|_| RconError::InvalidPort(port.clone()))?,
+            None => 25575,
+        };
+
+        Ok(Some(Self { port, password }))
+    }
+}
+
+#[derive(Debug)]
+pub struct RconClient {
+    stream: TcpStream,
+    next_id: i32,
+}
+
+impl RconClient {
+    /// Connects and logs in to a local server
+    pub fn connect(settings: &RconSettings, timeout: Duration) -> Result<Self, RconError> {
+        let addr = SocketAddr::from((Ipv4Addr::LOCALHOST, settings.port));
+        Self::connect_addr(addr, &settings.password, timeout)
+    }
+
+    pub fn connect_addr(
+        addr: SocketAddr,
+        password: &str,
+        timeout: Duration,
+    ) -> Result<Self, RconError> {
+        let stream = TcpStream::connect_timeout(&addr, timeout)?;
+        stream.set_read_timeout(Some(timeout))?;
+        stream.set_write_timeout(Some(timeout))?;
+
+        let mut client = Self { stream, next_id: 1 };
+
+        let id = client.send(PACKET_LOGIN, password)?;
+        let (response_id, _) = client.receive()?;
+
+        // Failed logins are answered with an ID of -1
+        if response_id != id {
+            return Err(RconError::AuthFailed);
+        }
+
+        Ok(client)
+    }
+
+    /// Runs a console command, returning its output
+    pub fn command(&mut self, command: &str) -> Result<String, RconError> {
+        if command.len() > MAX_COMMAND_LEN {
+            return Err(RconError::CommandTooLong);
+        }
+
+        let id = self.send(PACKET_COMMAND, command)?;
+
+        // Long output is split over several packets, with no marker for the last one. Servers
+        // answer requests in order, so an empty follow-up packet marks the end of the output.
+        let end_id = self.send(PACKET_RESPONSE, "")?;
+
+        let mut output = String::new();
+        loop {
+            let (response_id, body) = self.receive()?;
+            if response_id == end_id {
+                break;
+            }
+
+            if response_id == id {
+                output.push_str(&body);
+            }
+        }
+
+        Ok(output)
+    }
+
+    fn send(&mut self, kind: i32, body: &str) -> Result<i32, RconError> {
+        let id = self.next_id;
+        self.next_id += 1;
+
+        // ID, type, null-terminated body and an empty null-terminated string
+        let length = 4 + 4 + body.len() + 2;
+
+        let mut packet = Vec::with_capacity(4 + length);
+        packet.extend_from_slice(&(length as i32).to_le_bytes());
+        packet.extend_from_slice(&id.to_le_bytes());
+        packet.extend_from_slice(&kind.to_le_bytes());
+        packet.extend_from_slice(body.as_bytes());
+        packet.extend_from_slice(&[0, 0]);
+
+        self.stream.write_all(&packet)?;
+        Ok(id)
+    }
+
+    fn receive(&mut self) -> Result<(i32, String), RconError> {
+        let length = read_i32(&mut self.stream)?;
+        if !(10..=MAX_PACKET_LEN).contains(&length) {
+            return Err(RconError::InvalidPacket(length));
+        }
+
+        let mut packet = vec![0; length as usize];
+        self.stream.read_exact(&mut packet)?;
+
+        let mut packet = packet.as_slice();
+        let id = read_i32(&mut packet)?;
+        let _kind = read_i32(&mut packet)?;
+
+        // Strip both null terminators
+        let body = &packet[..packet.len() - 2];
+        let body = String::from_utf8_lossy(body).into_owned();
+
+        Ok((id, body))
+    }
+}
+
+fn read_i32<R: Read>(reader: &mut R) -> io::Result<i32> {
+    let mut bytes = [0; 4];
+    reader.read_exact(&mut bytes)?;
+
+    Ok(i32::from_le_bytes(bytes))
+}
+
+#[cfg(test)]
+mod tests {
+    use std::net::TcpListener;
+    use std::thread;
+
+    use super::*;
+
+    fn read_packet(stream: &mut TcpStream) -> (i32, i32, String) {
+        let length = read_i32(stream).unwrap();
+        let mut packet = vec![0; length as usize];
+        stream.read_exact(&mut packet).unwrap();
+
+        let mut packet = packet.as_slice();
+        let id = read_i32(&mut packet).unwrap();
+        let kind = read_i32(&mut packet).unwrap();
+        let body = String::from_utf8(packet[..packet.len() - 2].to_vec()).unwrap();
+
+        (id, kind, body)
+    }
+
+    fn write_packet(stream: &mut TcpStream, id: i32, body: &str) {
+        let mut packet = vec![];
+        packet.extend_from_slice(&((body.len() + 10) as i32).to_le_bytes());
+        packet.extend_from_slice(&id.to_le_bytes());
+        packet.extend_from_slice(&PACKET_RESPONSE.to_le_bytes());
+        packet.extend_from_slice(body.as_bytes());
+        packet.extend_from_slice(&[0, 0]);
+
+        stream.write_all(&packet).unwrap();
+    }
+
+    #[test]
+    fn test_rcon_fake_server() {
+        let listener = TcpListener::bind("127.0.0.1:0").unwrap();
+        let addr = listener.local_addr().unwrap();
+
+        let server = thread::spawn(move || {
+            let (mut stream, _) = listener.accept().unwrap();
+
+            let (id, kind, password) = read_packet(&mut stream);
+            assert_eq!((kind, password.as_str()), (PACKET_LOGIN, "hunter2"));
+            write_packet(&mut stream, id, "");
+
+            // Split the reply over two packets, like long command output
+            let (id, kind, command) = read_packet(&mut stream);
+            assert_eq!((kind, command.as_str()), (PACKET_COMMAND, "list"));
+            let (end_id, _, _) = read_packet(&mut stream);
+
+            write_packet(&mut stream, id, "There are 0 of a max ");
+            write_packet(&mut stream, id, "of 20 players online");
+            write_packet(&mut stream, end_id, "Unknown request 0");
+        });
+
+        let mut client = RconClient::connect_addr(addr, "hunter2", Duration::from_secs(5)).unwrap();
+        let output = client.command("list").unwrap();
+        server.join().unwrap();
+
+        assert_eq!(output, "There are 0 of a max of 20 players online");
+    }
+
+    #[test]
+    fn test_rcon_auth_failed() {
+        let listener = TcpListener::bind("127.0.0.1:0").unwrap();
+        let addr = listener.local_addr().unwrap();
+
+        let server = thread::spawn(move || {
+            let (mut stream, _) = listener.accept().unwrap();
+            read_packet(&mut stream);
+            write_packet(&mut stream, -1, "");
+        });
+
+        let result = RconClient::connect_addr(addr, "wrong", Duration::from_secs(5));
+        server.join().unwrap();
+
+        assert!(matches!(result, Err(RconError::AuthFailed)));
+    }
+}
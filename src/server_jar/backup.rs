@@ -0,0 +1,142 @@
+use std::fs;
+use std::path::{Path, PathBuf};
+use std::time::{SystemTime, UNIX_EPOCH};
+
+use color_eyre::Result;
+use strum::IntoEnumIterator;
+
+use super::JarManifest;
+use crate::arg_types::JarType;
+
+const BACKUP_DIR: &str = "jar_backups";
+
+/// Number of previous builds kept in each server directory
+const MAX_BACKUPS: usize = 3;
+
+/// A previously installed server .jar, kept alongside the manifest it was installed with
+#[derive(Debug)]
+pub struct JarBackup {
+    pub manifest: JarManifest,
+    jar_path: PathBuf,
+    manifest_path: PathBuf,
+}
+
+impl JarBackup {
+    /// Lists backups in a server directory, oldest first
+    pub fn list(directory: &Path) -> Result<Vec<Self>> {
+        let backup_dir = directory.join(BACKUP_DIR);
+        if !backup_dir.exists() {
+            return Ok(vec![]);
+        }
+
+        let mut manifest_paths = fs::read_dir(&backup_dir)?
+            .map(|entry| entry.map(|entry| entry.path()))
+            .collect::<Result<Vec<_>, _>>()?;
+
+        manifest_paths.retain(|path| path.extension().is_some_and(|ext| ext == "toml"));
+
+        // File names are prefixed with a zero-padded timestamp, so they sort by age
+        manifest_paths.sort();
+
+        let mut backups = Vec::with_capacity(manifest_paths.len());
+        for manifest_path in manifest_paths {
+            let jar_path = manifest_path.with_extension("jar");
+            if !jar_path.exists() {
+                continue;
+            }
+
+            let file = fs::read(&manifest_path)?;
+            backups.push(Self {
+                manifest: toml::from_slice(&file)?,
+                jar_path,
+                manifest_path,
+            });
+        }
+
+        Ok(backups)
+    }
+
+    /// Copies the currently installed .jar into the backup directory
+    ///
+    /// A .jar installed without a manifest is backed up with its type taken from the file name
+    /// and an unknown version and build. Does nothing if there is no installed .jar
+    pub fn create(directory: &Path) -> Result<Option<JarManifest>> {
+        let manifest = match JarManifest::read(directory)? {
+            Some(manifest) => manifest,
+            None => match Self::unknown_manifest(directory)? {
+                Some(manifest) => manifest,
+                None => return Ok(None),
+            },
+        };
+
+        let jar_path = directory.join(manifest.jar_type.file_name());
+        if !jar_path.exists() {
+            return Ok(None);
+        }
+
+        let backup_dir = directory.join(BACKUP_DIR);
+        fs::create_dir_all(&backup_dir)?;
+
+        let timestamp = SystemTime::now().duration_since(UNIX_EPOCH)?.as_millis();
+        let name = format!(
+            "{:016}_{}-{}-{}",
+            timestamp, manifest.jar_type, manifest.version, manifest.build
+        )
+        .replace(['/', '\\'], "_");
+
+        fs::copy(&jar_path, backup_dir.join(format!("{}.jar", name)))?;
+        fs::write(
+            backup_dir.join(format!("{}.toml", name)),
+            toml::to_string(&manifest)?,
+        )?;
+
+        let backups = Self::list(directory)?;
+        let excess = backups.len().saturating_sub(MAX_BACKUPS);
+        for backup in backups.into_iter().take(excess) {
+            backup.remove()?;
+        }
+
+        Ok(Some(manifest))
+    }
+
+    /// Moves the backup back into place as the installed .jar, and removes it from the backups
+    pub fn restore(self, directory: &Path) -> Result<JarManifest> {
+        // Renaming within the same directory tree replaces the installed .jar atomically
+        fs::rename(
+            &self.jar_path,
+            directory.join(self.manifest.jar_type.file_name()),
+        )?;
+
+        fs::remove_file(&self.manifest_path)?;
+        self.manifest.write(directory)?;
+
+        Ok(self.manifest)
+    }
+
+    /// Describes the first known server .jar found in a directory that has no manifest
+    fn unknown_manifest(directory: &Path) -> Result<Option<JarManifest>> {
+        for jar_type in JarType::iter() {
+            let jar_path = directory.join(jar_type.file_name());
+            if !jar_path.exists() {
+                continue;
+            }
+
+            let bytes = fs::read(&jar_path)?;
+            return Ok(Some(JarManifest {
+                jar_type,
+                version: "unknown".to_owned(),
+                build: "unknown".to_owned(),
+                sha256: super::sha256_hex(&bytes),
+            }));
+        }
+
+        Ok(None)
+    }
+
+    fn remove(self) -> Result<()> {
+        fs::remove_file(self.jar_path)?;
+        fs::remove_file(self.manifest_path)?;
+
+        Ok(())
+    }
+}
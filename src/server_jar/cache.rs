@@ -0,0 +1,281 @@
+use std::cmp::Ordering;
+use std::collections::{BTreeMap, HashSet};
+use std::fs;
+use std::path::{Path, PathBuf};
+
+use bytes::Bytes;
+use color_eyre::Result;
+use tracing::warn;
+
+use super::ServerJar;
+use crate::arg_types::JarType;
+
+/// Content-addressed store of downloaded server .jar files
+///
+/// Each .jar is stored once as `objects/<sha256>.jar` and referenced by
+/// `index/<jar type>/<version>/<build>`, which contains the .jar's hash.
+#[derive(Debug)]
+pub struct JarCache {
+    root: PathBuf,
+}
+
+#[derive(Debug, Clone)]
+pub struct CacheEntry {
+    pub jar_type: String,
+    pub version: String,
+    pub build: String,
+    pub sha256: String,
+    pub size: Option<u64>,
+}
+
+impl JarCache {
+    pub fn new(root: impl Into<PathBuf>) -> Self {
+        Self { root: root.into() }
+    }
+
+    pub fn root(&self) -> &Path {
+        &self.root
+    }
+
+    fn object_path(&self, sha256: &str) -> PathBuf {
+        self.root.join("objects").join(format!("{}.jar", sha256))
+    }
+
+    fn version_index(&self, jar_type: &str, version: &str) -> PathBuf {
+        self.root.join("index").join(jar_type).join(version)
+    }
+
+    pub fn get(&self, jar_type: JarType, version: &str, build: &str) -> Result<Option<ServerJar>> {
+        let index = self
+            .version_index(&jar_type.to_string(), version)
+            .join(build);
+        if !index.is_file() {
+            return Ok(None);
+        }
+
+        let sha256 = fs::read_to_string(&index)?.trim().to_owned();
+        let object = self.object_path(&sha256);
+        if !object.is_file() {
+            return Ok(None);
+        }
+
+        let bytes = fs::read(&object)?;
+        if super::sha256_hex(&bytes) != sha256 {
+            warn!(
+                "cached {} build {} for {} is corrupt, discarding",
+                jar_type.file_name(),
+                build,
+                version
+            );
+
+            fs::remove_file(&object)?;
+            fs::remove_file(&index)?;
+            return Ok(None);
+        }
+
+        let jar = ServerJar {
+            build: build.to_owned(),
+            sha256,
+            bytes: Bytes::from(bytes),
+        };
+
+        Ok(Some(jar))
+    }
+
+    /// Newest cached build for a given version that `accept` allows, used when the build API is
+    /// unreachable
+    pub fn latest(
+        &self,
+        jar_type: JarType,
+        version: &str,
+        accept: impl Fn(&str) -> bool,
+    ) -> Result<Option<ServerJar>> {
+        let dir = self.version_index(&jar_type.to_string(), version);
+        if !dir.is_dir() {
+            return Ok(None);
+        }
+
+        let mut builds = list_dir(&dir)?;
+        builds.retain(|build| accept(build));
+        builds.sort_by(|a, b| compare_builds(a, b));
+
+        for build in builds.iter().rev() {
+            if let Some(jar) = self.get(jar_type, version, build)? {
+                return Ok(Some(jar));
+            }
+        }
+
+        Ok(None)
+    }
+
+    pub fn insert(&self, jar_type: JarType, version: &str, jar: &ServerJar) -> Result<()> {
+        let object = self.object_path(&jar.sha256);
+        if !object.exists() {
+            fs::create_dir_all(object.parent().unwrap())?;
+            super::write_atomic(&object, &jar.bytes)?;
+        }
+
+        let dir = self.version_index(&jar_type.to_string(), version);
+        fs::create_dir_all(&dir)?;
+        fs::write(dir.join(&jar.build), format!("{}\n", jar.sha256))?;
+
+        Ok(())
+    }
+
+    pub fn entries(&self) -> Result<Vec<CacheEntry>> {
+        let index = self.root.join("index");
+        if !index.is_dir() {
+            return Ok(vec![]);
+        }
+
+        let mut entries = vec![];
+        for jar_type in list_dir(&index)? {
+            for version in list_dir(&index.join(&jar_type))? {
+                let dir = self.version_index(&jar_type, &version);
+                for build in list_dir(&dir)? {
+                    let sha256 = fs::read_to_string(dir.join(&build))?.trim().to_owned();
+                    let size = fs::metadata(self.object_path(&sha256))
+                        .ok()
+                        .map(|metadata| metadata.len());
+
+                    entries.push(CacheEntry {
+                        jar_type: jar_type.clone(),
+                        version: version.clone(),
+                        build,
+                        sha256,
+                        size,
+                    });
+                }
+            }
+        }
+
+        entries.sort_by(|a, b| {
+            a.jar_type
+                .cmp(&b.jar_type)
+                .then_with(|| a.version.cmp(&b.version))
+                .then_with(|| compare_builds(&a.build, &b.build))
+        });
+
+        Ok(entries)
+    }
+
+    /// Keeps the newest `keep` builds of each jar type and version,
+    /// then removes any .jar no longer referenced by the index
+    pub fn prune(&self, keep: usize) -> Result<Vec<CacheEntry>> {
+        let mut grouped = BTreeMap::<(String, String), Vec<CacheEntry>>::new();
+        for entry in self.entries()? {
+            grouped
+                .entry((entry.jar_type.clone(), entry.version.clone()))
+                .or_default()
+                .push(entry);
+        }
+
+        let mut removed = vec![];
+        let mut referenced = HashSet::new();
+        for ((jar_type, version), entries) in grouped {
+            // Entries are sorted oldest first, and dangling entries are always removed
+            let (dangling, mut entries): (Vec<_>, Vec<_>) =
+                entries.into_iter().partition(|entry| entry.size.is_none());
+
+            let split = entries.len().saturating_sub(keep);
+            let kept = entries.split_off(split);
+
+            for entry in dangling.into_iter().chain(entries) {
+                fs::remove_file(self.version_index(&jar_type, &version).join(&entry.build))?;
+                removed.push(entry);
+            }
+
+            referenced.extend(kept.into_iter().map(|entry| entry.sha256));
+        }
+
+        let objects = self.root.join("objects");
+        if objects.is_dir() {
+            for file_name in list_dir(&objects)? {
+                let sha256 = file_name.trim_end_matches(".jar");
+                if !referenced.contains(sha256) {
+                    fs::remove_file(objects.join(&file_name))?;
+                }
+            }
+        }
+
+        Ok(removed)
+    }
+
+    pub fn clear(&self) -> Result<()> {
+        if self.root.exists() {
+            fs::remove_dir_all(&self.root)?;
+        }
+
+        Ok(())
+    }
+}
+
+fn list_dir(dir: &Path) -> Result<Vec<String>> {
+    let mut names = vec![];
+    for entry in fs::read_dir(dir)? {
+        let entry = entry?;
+        names.push(entry.file_name().to_string_lossy().into_owned());
+    }
+
+    Ok(names)
+}
+
+fn compare_builds(a: &str, b: &str) -> Ordering {
+    match (a.parse::<u64>(), b.parse::<u64>()) {
+        (Ok(a), Ok(b)) => a.cmp(&b),
+        _ => a.cmp(b),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use bytes::Bytes;
+
+    use super::JarCache;
+    use crate::arg_types::JarType;
+    use crate::server_jar::{self, ServerJar};
+
+    fn jar(build: &str, contents: &'static [u8]) -> ServerJar {
+        ServerJar {
+            build: build.into(),
+            sha256: server_jar::sha256_hex(contents),
+            bytes: Bytes::from_static(contents),
+        }
+    }
+
+    #[test]
+    fn test_cache_prune() {
+        let root = std::env::temp_dir().join(format!("provisioner_cache_{}", std::process::id()));
+        let cache = JarCache::new(&root);
+
+        cache
+            .insert(JarType::Paper, "1.18.1", &jar("9", b"old"))
+            .unwrap();
+        cache
+            .insert(JarType::Paper, "1.18.1", &jar("10", b"new"))
+            .unwrap();
+        cache
+            .insert(JarType::Paper, "1.17.1", &jar("3", b"new"))
+            .unwrap();
+
+        let latest = cache
+            .latest(JarType::Paper, "1.18.1", |_| true)
+            .unwrap()
+            .unwrap();
+        assert_eq!(latest.build, "10");
+
+        let filtered = cache.latest(JarType::Paper, "1.18.1", |build| build == "9");
+        assert_eq!(filtered.unwrap().unwrap().build, "9");
+
+        let removed = cache.prune(1).unwrap();
+        assert_eq!(removed.len(), 1);
+        assert_eq!(removed[0].build, "9");
+
+        assert!(cache.get(JarType::Paper, "1.18.1", "9").unwrap().is_none());
+        assert!(cache.get(JarType::Paper, "1.17.1", "3").unwrap().is_some());
+        assert_eq!(cache.entries().unwrap().len(), 2);
+
+        cache.clear().unwrap();
+        assert!(!root.exists());
+    }
+}
@@ -0,0 +1,102 @@
+use std::path::Path;
+
+use color_eyre::Result;
+
+use super::{BuildInfo, Checksum, JarBuild, JarOptions, ServerJarError, ServerJarProvider};
+use crate::arg_types::JarType;
+
+pub struct CustomJarProvider;
+impl ServerJarProvider for CustomJarProvider {
+    fn resolve_build(_: &str, build: Option<&str>, options: &JarOptions) -> Result<JarBuild> {
+        let source = options
+            .jar_source
+            .clone()
+            .ok_or(ServerJarError::MissingJarSource)?;
+
+        let file_name = match Path::new(&source).file_name() {
+            Some(file_name) => file_name.to_string_lossy().into_owned(),
+            None => source.clone(),
+        };
+
+        // Custom builds have no upstream build number, so the pinned build is only a label
+        let build = JarBuild {
+            build: build.unwrap_or("custom").to_owned(),
+            file_name,
+            download_url: source,
+            checksum: options.jar_checksum.clone().map(Checksum::Sha256),
+        };
+
+        Ok(build)
+    }
+
+    fn versions() -> Result<Vec<String>> {
+        Err(ServerJarError::NoBuildApi(JarType::Custom).into())
+    }
+
+    fn builds(_: &str, _: usize, _: &JarOptions) -> Result<Vec<BuildInfo>> {
+        Err(ServerJarError::NoBuildApi(JarType::Custom).into())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::fs;
+
+    use super::CustomJarProvider;
+    use crate::arg_types::JarType;
+    use crate::server_jar::{self, test_server, JarOptions, ServerJarProvider};
+
+    fn source(jar_source: String, jar_checksum: &str) -> JarOptions {
+        JarOptions {
+            jar_source: Some(jar_source),
+            jar_checksum: Some(jar_checksum.to_owned()),
+            ..Default::default()
+        }
+    }
+
+    #[test]
+    fn test_custom_jar_sources() {
+        let root = std::env::temp_dir().join(format!("provisioner_custom_{}", std::process::id()));
+        fs::create_dir_all(&root).unwrap();
+
+        let path = root.join("server.jar");
+        fs::write(&path, b"custom jar").unwrap();
+
+        let sha256 = server_jar::sha256_hex(b"custom jar");
+        let local = path.to_str().unwrap().to_owned();
+        let build =
+            CustomJarProvider::resolve_build("1.18.1", None, &source(local.clone(), &sha256))
+                .unwrap();
+        assert_eq!(build.build, "custom");
+        assert_eq!(build.file_name, "server.jar");
+
+        let jar = server_jar::download_build(JarType::Custom, "1.18.1", &build).unwrap();
+        assert_eq!(jar.sha256, sha256);
+
+        let bad_checksum =
+            CustomJarProvider::resolve_build("1.18.1", None, &source(local, "00")).unwrap();
+        assert!(server_jar::download_build(JarType::Custom, "1.18.1", &bad_checksum).is_err());
+
+        let missing = root.join("missing.jar").to_str().unwrap().to_owned();
+        let missing =
+            CustomJarProvider::resolve_build("1.18.1", None, &source(missing, &sha256)).unwrap();
+        assert!(server_jar::download_build(JarType::Custom, "1.18.1", &missing).is_err());
+
+        let base_url = test_server::serve(JarType::Custom, vec![("/server.jar", "custom jar")]);
+        let remote = CustomJarProvider::resolve_build(
+            "1.18.1",
+            Some("7"),
+            &source(format!("{}/server.jar", base_url), &sha256),
+        )
+        .unwrap();
+        assert_eq!(remote.build, "7");
+        assert_eq!(remote.file_name, "server.jar");
+
+        let jar = server_jar::download_build(JarType::Custom, "1.18.1", &remote).unwrap();
+        assert_eq!(jar.sha256, sha256);
+
+        assert!(CustomJarProvider::resolve_build("1.18.1", None, &JarOptions::default()).is_err());
+
+        fs::remove_dir_all(&root).unwrap();
+    }
+}
@@ -0,0 +1,226 @@
+use color_eyre::Result;
+use serde::Deserialize;
+
+use super::http::{api_urls, CLIENT};
+use super::{BuildInfo, JarBuild, JarOptions, ServerJarError, ServerJarProvider};
+use crate::arg_types::JarType;
+
+#[derive(Debug, Deserialize)]
+struct FabricLoaderResponse {
+    loader: FabricComponent,
+}
+
+#[derive(Debug, Deserialize)]
+#[allow(dead_code)]
+struct FabricComponent {
+    version: String,
+    stable: bool,
+}
+
+fn loaders(version: &str) -> Result<Vec<FabricLoaderResponse>> {
+    let url = format!("{}/versions/loader/{}", api_urls().fabric, version);
+    let loaders = CLIENT
+        .get(url)
+        .send()?
+        .error_for_status()?
+        .json::<Vec<FabricLoaderResponse>>()?;
+
+    // Unsupported game versions return an empty list
+    if loaders.is_empty() {
+        return Err(ServerJarError::VersionNotFound {
+            jar_type: JarType::Fabric,
+            version: version.to_owned(),
+        }
+        .into());
+    }
+
+    Ok(loaders)
+}
+
+fn loader_version(version: &str, loader: Option<&str>) -> Result<String> {
+    let loaders = loaders(version)?;
+
+    // Loaders are listed newest first
+    let loader = match loader {
+        None => loaders.into_iter().next().unwrap().loader.version,
+        Some(loader) if loaders.iter().any(|l| l.loader.version == loader) => loader.to_owned(),
+        Some(loader) => {
+            return Err(ServerJarError::FabricVersionNotFound {
+                component: "loader",
+                requested: loader.to_owned(),
+                version: version.to_owned(),
+            }
+            .into())
+        }
+    };
+
+    Ok(loader)
+}
+
+fn installer_version(version: &str, installer: Option<&str>) -> Result<String> {
+    let url = format!("{}/versions/installer", api_urls().fabric);
+    let installers = CLIENT
+        .get(url)
+        .send()?
+        .error_for_status()?
+        .json::<Vec<FabricComponent>>()?;
+
+    let found = match installer {
+        None => installers.into_iter().next().map(|i| i.version),
+        Some(installer) => installers
+            .into_iter()
+            .find(|i| i.version == installer)
+            .map(|i| i.version),
+    };
+
+    found.ok_or_else(|| {
+        ServerJarError::FabricVersionNotFound {
+            component: "installer",
+            requested: installer.unwrap_or("latest").to_owned(),
+            version: version.to_owned(),
+        }
+        .into()
+    })
+}
+
+pub struct FabricJarProvider;
+impl ServerJarProvider for FabricJarProvider {
+    fn resolve_build(version: &str, build: Option<&str>, options: &JarOptions) -> Result<JarBuild> {
+        let loader = loader_version(version, options.fabric_loader_version.as_deref())?;
+        let installer = installer_version(version, options.fabric_installer_version.as_deref())?;
+
+        // Fabric has no build numbers, so builds are identified by loader and installer
+        let build_id = format!("{}-{}", loader, installer);
+        if let Some(build) = build {
+            if build != build_id {
+                return Err(ServerJarError::BuildNotFound {
+                    jar_type: JarType::Fabric,
+                    version: version.to_owned(),
+                    build: build.to_owned(),
+                }
+                .into());
+            }
+        }
+
+        let download_url = format!(
+            "{}/versions/loader/{}/{}/{}/server/jar",
+            api_urls().fabric,
+            version,
+            loader,
+            installer
+        );
+
+        let build = JarBuild {
+            file_name: format!("fabric-server-{}-{}.jar", version, build_id),
+            build: build_id,
+            download_url,
+            checksum: None,
+        };
+
+        Ok(build)
+    }
+
+    fn versions() -> Result<Vec<String>> {
+        let url = format!("{}/versions/game", api_urls().fabric);
+        let versions = CLIENT
+            .get(url)
+            .send()?
+            .error_for_status()?
+            .json::<Vec<FabricComponent>>()?
+            .into_iter()
+            .filter(|version| version.stable)
+            .map(|version| version.version)
+            .rev()
+            .collect();
+
+        Ok(versions)
+    }
+
+    /// Lists recent loaders, paired with the configured (or latest) installer
+    fn builds(version: &str, limit: usize, options: &JarOptions) -> Result<Vec<BuildInfo>> {
+        let installer = installer_version(version, options.fabric_installer_version.as_deref())?;
+        let builds = loaders(version)?
+            .into_iter()
+            .take(limit)
+            .map(|loader| BuildInfo {
+                build: format!("{}-{}", loader.loader.version, installer),
+                time: None,
+                changes: vec![],
+            })
+            .collect();
+
+        Ok(builds)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::FabricJarProvider;
+    use crate::arg_types::JarType;
+    use crate::server_jar::{self, test_server, JarOptions, ServerJarProvider};
+
+    #[test]
+    fn test_fabric_resolve_build() {
+        let base_url = test_server::serve(
+            JarType::Fabric,
+            vec![
+                (
+                    "/versions/loader/1.18.1",
+                    r#"[{"loader":{"version":"0.13.3","stable":true}},{"loader":{"version":"0.12.12","stable":true}}]"#,
+                ),
+                // Unsupported game versions return an empty list
+                ("/versions/loader/1.0", "[]"),
+                (
+                    "/versions/installer",
+                    r#"[{"version":"0.10.2","stable":true},{"version":"0.10.1","stable":true}]"#,
+                ),
+                (
+                    "/versions/loader/1.18.1/0.13.3/0.10.2/server/jar",
+                    "fabric jar",
+                ),
+            ],
+        );
+
+        let latest =
+            FabricJarProvider::resolve_build("1.18.1", None, &JarOptions::default()).unwrap();
+        assert_eq!(latest.build, "0.13.3-0.10.2");
+        assert_eq!(latest.file_name, "fabric-server-1.18.1-0.13.3-0.10.2.jar");
+        assert_eq!(
+            latest.download_url,
+            format!(
+                "{}/versions/loader/1.18.1/0.13.3/0.10.2/server/jar",
+                base_url
+            )
+        );
+
+        let jar = server_jar::download_build(JarType::Fabric, "1.18.1", &latest).unwrap();
+        assert_eq!(jar.sha256, server_jar::sha256_hex(b"fabric jar"));
+
+        let pinned = JarOptions {
+            fabric_loader_version: Some("0.12.12".to_owned()),
+            fabric_installer_version: Some("0.10.1".to_owned()),
+            ..Default::default()
+        };
+
+        let build = FabricJarProvider::resolve_build("1.18.1", None, &pinned).unwrap();
+        assert_eq!(build.build, "0.12.12-0.10.1");
+        assert!(
+            FabricJarProvider::resolve_build("1.18.1", Some("0.13.3-0.10.2"), &pinned).is_err()
+        );
+
+        for options in [
+            JarOptions {
+                fabric_loader_version: Some("0.1.0".to_owned()),
+                ..Default::default()
+            },
+            JarOptions {
+                fabric_installer_version: Some("0.1.0".to_owned()),
+                ..Default::default()
+            },
+        ] {
+            assert!(FabricJarProvider::resolve_build("1.18.1", None, &options).is_err());
+        }
+
+        assert!(FabricJarProvider::resolve_build("1.0", None, &JarOptions::default()).is_err());
+    }
+}
@@ -1,10 +1,90 @@
-use once_cell::sync::Lazy;
+use std::time::Duration;
+
+use color_eyre::Result;
+use once_cell::sync::{Lazy, OnceCell};
 use reqwest::blocking::{Client, ClientBuilder};
+use reqwest::Proxy;
+
+use super::ServerJarError;
 
 static APP_USER_AGENT: &str = concat!(env!("CARGO_PKG_NAME"), "/", env!("CARGO_PKG_VERSION"),);
-pub(super) static CLIENT: Lazy<Client> = Lazy::new(|| {
-    ClientBuilder::new()
-        .user_agent(APP_USER_AGENT)
-        .build()
-        .unwrap()
-});
+static OPTIONS: OnceCell<HttpOptions> = OnceCell::new();
+
+pub(super) static CLIENT: Lazy<Client> = Lazy::new(|| build_client(options()).unwrap());
+
+#[derive(Debug, Clone, Default)]
+pub struct HttpOptions {
+    pub proxy: Option<String>,
+    pub timeout_secs: Option<u64>,
+    pub api_urls: ApiUrls,
+}
+
+/// Base URLs for each jar provider's build API
+#[derive(Debug, Clone)]
+pub struct ApiUrls {
+    pub paper: String,
+    pub pufferfish: String,
+    pub purpur: String,
+    pub vanilla: String,
+    pub fabric: String,
+}
+
+impl Default for ApiUrls {
+    fn default() -> Self {
+        Self {
+            paper: "https://papermc.io/api/v2".into(),
+            pufferfish: "https://ci.pufferfish.host".into(),
+            purpur: "https://api.purpurmc.org/v2".into(),
+            vanilla: "https://launchermeta.mojang.com".into(),
+            fabric: "https://meta.fabricmc.net/v2".into(),
+        }
+    }
+}
+
+fn options() -> &'static HttpOptions {
+    OPTIONS.get_or_init(Default::default)
+}
+
+pub(super) fn api_urls() -> &'static ApiUrls {
+    &options().api_urls
+}
+
+fn build_client(options: &HttpOptions) -> reqwest::Result<Client> {
+    let mut builder = ClientBuilder::new().user_agent(APP_USER_AGENT);
+
+    if let Some(proxy) = &options.proxy {
+        builder = builder.proxy(Proxy::all(proxy)?);
+    }
+
+    if let Some(timeout_secs) = options.timeout_secs {
+        builder = builder.timeout(Duration::from_secs(timeout_secs));
+    }
+
+    builder.build()
+}
+
+/// Sets the options used by the shared HTTP client
+///
+/// Fails if called twice, or after a request has already been made with the default options
+pub fn configure(mut options: HttpOptions) -> Result<()> {
+    // Build once up front so invalid proxy URLs are reported instead of panicking later
+    build_client(&options)?;
+
+    let urls = &mut options.api_urls;
+    for url in [
+        &mut urls.paper,
+        &mut urls.pufferfish,
+        &mut urls.purpur,
+        &mut urls.vanilla,
+        &mut urls.fabric,
+    ] {
+        let trimmed = url.trim_end_matches('/').len();
+        url.truncate(trimmed);
+    }
+
+    OPTIONS
+        .set(options)
+        .map_err(|_| ServerJarError::HttpAlreadyConfigured)?;
+
+    Ok(())
+}
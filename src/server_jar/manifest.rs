@@ -0,0 +1,62 @@
+use std::fs;
+use std::path::Path;
+
+use color_eyre::Result;
+use serde::{Deserialize, Serialize};
+
+use super::ServerJar;
+use crate::arg_types::JarType;
+
+const MANIFEST_FILE: &str = "jar_manifest.toml";
+
+/// Records which server .jar was installed into a server directory
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct JarManifest {
+    pub jar_type: JarType,
+    pub version: String,
+    pub build: String,
+    pub sha256: String,
+}
+
+impl JarManifest {
+    pub fn new(jar_type: JarType, version: &str, jar: &ServerJar) -> Self {
+        Self {
+            jar_type,
+            version: version.to_owned(),
+            build: jar.build.clone(),
+            sha256: jar.sha256.clone(),
+        }
+    }
+
+    pub fn read(directory: &Path) -> Result<Option<Self>> {
+        let path = directory.join(MANIFEST_FILE);
+        if !path.exists() {
+            return Ok(None);
+        }
+
+        let file = fs::read(&path)?;
+        let manifest = toml::from_slice::<Self>(&file)?;
+
+        Ok(Some(manifest))
+    }
+
+    pub fn write(&self, directory: &Path) -> Result<()> {
+        let manifest = toml::to_string(self)?;
+        super::write_atomic(&directory.join(MANIFEST_FILE), manifest.as_bytes())?;
+
+        Ok(())
+    }
+
+    /// Checks the installed .jar still matches the recorded hash
+    pub fn verify(&self, directory: &Path) -> Result<bool> {
+        let path = directory.join(self.jar_type.file_name());
+        if !path.exists() {
+            return Ok(false);
+        }
+
+        let bytes = fs::read(&path)?;
+        let matches = super::sha256_hex(&bytes) == self.sha256;
+
+        Ok(matches)
+    }
+}
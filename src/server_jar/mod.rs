@@ -1,13 +1,245 @@
+use std::fs::{self, File};
+use std::io::{self, Write};
+use std::path::Path;
+
 use bytes::Bytes;
 use color_eyre::Result;
+use md5::Md5;
+use serde::Serialize;
+use sha1::Sha1;
+use sha2::{Digest, Sha256};
+use thiserror::Error;
+use tracing::info;
+
+use self::http::CLIENT;
+use crate::arg_types::JarType;
 
-mod http;
+mod backup;
+mod cache;
+mod custom;
+mod fabric;
+pub mod http;
+mod manifest;
 mod paper;
 mod pufferfish;
+mod purpur;
+#[cfg(test)]
+mod test_server;
+mod vanilla;
 
+pub use backup::JarBackup;
+pub use cache::JarCache;
+pub use custom::CustomJarProvider;
+pub use fabric::FabricJarProvider;
+pub use manifest::JarManifest;
 pub use paper::PaperJarProvider;
 pub use pufferfish::PufferfishJarProvider;
+pub use purpur::PurpurJarProvider;
+pub use vanilla::VanillaJarProvider;
 
 pub trait ServerJarProvider {
-    fn download_jar(version: &str) -> Result<Bytes>;
+    /// Resolves a specific build, or the latest build if `build` is `None`
+    fn resolve_build(version: &str, build: Option<&str>, options: &JarOptions) -> Result<JarBuild>;
+
+    /// Game versions supported by the build API
+    fn versions() -> Result<Vec<String>>;
+
+    /// Up to `limit` of the most recent builds for a version, newest first
+    fn builds(version: &str, limit: usize, options: &JarOptions) -> Result<Vec<BuildInfo>>;
+}
+
+#[derive(Debug, Clone, Serialize)]
+pub struct BuildInfo {
+    pub build: String,
+    pub time: Option<String>,
+    pub changes: Vec<String>,
+}
+
+/// Provider specific options, ignored by providers that don't need them
+#[derive(Debug, Clone, Default)]
+pub struct JarOptions {
+    pub fabric_loader_version: Option<String>,
+    pub fabric_installer_version: Option<String>,
+    pub jar_source: Option<String>,
+    pub jar_checksum: Option<String>,
+}
+
+#[derive(Debug, Clone)]
+pub struct JarBuild {
+    pub build: String,
+    pub file_name: String,
+    pub download_url: String,
+    pub checksum: Option<Checksum>,
+}
+
+/// Checksum published by a build API alongside a download
+#[derive(Debug, Clone)]
+pub enum Checksum {
+    Sha256(String),
+    Sha1(String),
+    Md5(String),
+}
+
+impl Checksum {
+    fn algorithm(&self) -> &'static str {
+        match self {
+            Checksum::Sha256(_) => "sha256",
+            Checksum::Sha1(_) => "sha1",
+            Checksum::Md5(_) => "md5",
+        }
+    }
+
+    fn verify(&self, file_name: &str, bytes: &[u8]) -> Result<(), ServerJarError> {
+        let (expected, actual) = match self {
+            Checksum::Sha256(expected) => (expected, sha256_hex(bytes)),
+            Checksum::Sha1(expected) => (expected, to_hex(&Sha1::digest(bytes))),
+            Checksum::Md5(expected) => (expected, to_hex(&Md5::digest(bytes))),
+        };
+
+        if !actual.eq_ignore_ascii_case(expected) {
+            return Err(ServerJarError::ChecksumMismatch {
+                file_name: file_name.to_owned(),
+                algorithm: self.algorithm(),
+                expected: expected.to_lowercase(),
+                actual,
+            });
+        }
+
+        Ok(())
+    }
+}
+
+#[derive(Debug, Clone)]
+pub struct ServerJar {
+    pub build: String,
+    pub sha256: String,
+    pub bytes: Bytes,
+}
+
+#[derive(Debug, Error)]
+pub enum ServerJarError {
+    #[error("checksum mismatch for {file_name}: expected {algorithm} {expected}, got {actual}")]
+    ChecksumMismatch {
+        file_name: String,
+        algorithm: &'static str,
+        expected: String,
+        actual: String,
+    },
+
+    #[error("{jar_type} does not support version {version}")]
+    VersionNotFound { jar_type: JarType, version: String },
+
+    #[error("fabric {component} version {requested} does not exist for version {version}")]
+    FabricVersionNotFound {
+        component: &'static str,
+        requested: String,
+        version: String,
+    },
+
+    #[error("{0} jars have no build API")]
+    NoBuildApi(JarType),
+
+    #[error("custom jar type requires a jar source path or url")]
+    MissingJarSource,
+
+    #[error("{jar_type} build {build} does not exist for version {version}")]
+    BuildNotFound {
+        jar_type: JarType,
+        version: String,
+        build: String,
+    },
+
+    #[error("the http client can only be configured once, before any requests are made")]
+    HttpAlreadyConfigured,
+}
+
+fn to_hex(digest: &[u8]) -> String {
+    digest.iter().map(|byte| format!("{:02x}", byte)).collect()
+}
+
+pub fn sha256_hex(bytes: &[u8]) -> String {
+    to_hex(&Sha256::digest(bytes))
+}
+
+pub fn is_url(source: &str) -> bool {
+    source.starts_with("http://") || source.starts_with("https://")
+}
+
+/// Fetches a build, which may be a local file for custom jars
+pub fn download_build(jar_type: JarType, version: &str, build: &JarBuild) -> Result<ServerJar> {
+    let bytes = if is_url(&build.download_url) {
+        info!(
+            "downloading {} build {} for {}",
+            jar_type.file_name(),
+            build.build,
+            version
+        );
+
+        CLIENT
+            .get(&build.download_url)
+            .send()?
+            .error_for_status()?
+            .bytes()?
+    } else {
+        info!(
+            "reading {} build {} from {:?}",
+            jar_type.file_name(),
+            build.build,
+            &build.download_url
+        );
+
+        Bytes::from(fs::read(&build.download_url)?)
+    };
+
+    if let Some(checksum) = &build.checksum {
+        checksum.verify(&build.file_name, &bytes)?;
+    }
+
+    let jar = ServerJar {
+        build: build.build.clone(),
+        sha256: sha256_hex(&bytes),
+        bytes,
+    };
+
+    Ok(jar)
+}
+
+/// Writes to a temporary file next to `path` and renames it into place,
+/// so a crash midway never leaves a truncated file behind
+fn write_atomic(path: &Path, bytes: &[u8]) -> io::Result<()> {
+    let file_name = path.file_name().unwrap().to_string_lossy();
+    let tmp_path = path.with_file_name(format!(".{}.tmp", file_name));
+
+    let mut file = File::create(&tmp_path)?;
+    file.write_all(bytes)?;
+    file.sync_all()?;
+
+    fs::rename(&tmp_path, path)
+}
+
+/// Writes the server .jar into a server directory and records its manifest
+///
+/// The previously installed build is kept as a backup so it can be rolled back to
+pub fn install_jar(
+    directory: &Path,
+    jar_type: JarType,
+    version: &str,
+    jar: &ServerJar,
+) -> Result<()> {
+    let manifest = JarManifest::new(jar_type, version, jar);
+
+    // Reinstalling the same build doesn't need another backup
+    if JarManifest::read(directory)?.as_ref() != Some(&manifest) {
+        if let Some(previous) = JarBackup::create(directory)? {
+            info!(
+                "backed up {} build {} for {} in {:?}",
+                previous.jar_type, previous.build, previous.version, directory
+            );
+        }
+    }
+
+    write_atomic(&directory.join(jar_type.file_name()), &jar.bytes)?;
+    manifest.write(directory)?;
+
+    Ok(())
 }
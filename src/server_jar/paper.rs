@@ -1,12 +1,12 @@
 use std::collections::HashMap;
 
-use bytes::Bytes;
 use color_eyre::Result;
+use reqwest::StatusCode;
 use serde::Deserialize;
-use tracing::info;
 
-use super::http::CLIENT;
-use super::ServerJarProvider;
+use super::http::{api_urls, CLIENT};
+use super::{BuildInfo, Checksum, JarBuild, JarOptions, ServerJarError, ServerJarProvider};
+use crate::arg_types::JarType;
 
 #[derive(Debug, Deserialize)]
 #[allow(dead_code)]
@@ -17,16 +17,45 @@ struct PaperVersionResponse {
     builds: Vec<u16>,
 }
 
-fn latest_paper_build(version: &str) -> Result<u16> {
-    let url = format!(
-        "https://papermc.io/api/v2/projects/paper/versions/{}",
-        version
-    );
+fn version_not_found(version: &str) -> ServerJarError {
+    ServerJarError::VersionNotFound {
+        jar_type: JarType::Paper,
+        version: version.to_owned(),
+    }
+}
+
+fn paper_build(version: &str, build: Option<&str>) -> Result<u16> {
+    let url = format!("{}/projects/paper/versions/{}", api_urls().paper, version);
 
-    let response = CLIENT.get(url).send()?.json::<PaperVersionResponse>()?;
-    let latest_build = response.builds.into_iter().max().unwrap();
+    let response = CLIENT.get(url).send()?;
+    if response.status() == StatusCode::NOT_FOUND {
+        return Err(version_not_found(version).into());
+    }
+
+    let response = response
+        .error_for_status()?
+        .json::<PaperVersionResponse>()?;
+
+    let build_id = match build {
+        None => response
+            .builds
+            .into_iter()
+            .max()
+            .ok_or_else(|| version_not_found(version))?,
+        Some(build) => match build.parse::<u16>() {
+            Ok(build_id) if response.builds.contains(&build_id) => build_id,
+            _ => {
+                return Err(ServerJarError::BuildNotFound {
+                    jar_type: JarType::Paper,
+                    version: version.to_owned(),
+                    build: build.to_owned(),
+                }
+                .into())
+            }
+        },
+    };
 
-    Ok(latest_build)
+    Ok(build_id)
 }
 
 #[derive(Debug, Deserialize)]
@@ -46,32 +75,174 @@ struct PaperBuildDownload {
     sha256: String,
 }
 
-fn paper_url(version: &str, build_id: u16) -> Result<String> {
+fn paper_download(version: &str, build_id: u16) -> Result<(String, PaperBuildDownload)> {
     let url = format!(
-        "https://papermc.io/api/v2/projects/paper/versions/{}/builds/{}",
-        version, build_id
+        "{}/projects/paper/versions/{}/builds/{}",
+        api_urls().paper,
+        version,
+        build_id
     );
 
-    let response = CLIENT.get(url).send()?.json::<PaperBuildResponse>()?;
-    let file_name = &response.downloads.get("application").unwrap().name;
+    let mut response = CLIENT
+        .get(url)
+        .send()?
+        .error_for_status()?
+        .json::<PaperBuildResponse>()?;
+    let download =
+        response
+            .downloads
+            .remove("application")
+            .ok_or_else(|| ServerJarError::BuildNotFound {
+                jar_type: JarType::Paper,
+                version: version.to_owned(),
+                build: build_id.to_string(),
+            })?;
 
     let url = format!(
-        "https://papermc.io/api/v2/projects/paper/versions/{}/builds/{}/downloads/{}",
-        version, build_id, file_name
+        "{}/projects/paper/versions/{}/builds/{}/downloads/{}",
+        api_urls().paper,
+        version,
+        build_id,
+        download.name
     );
 
-    Ok(url)
+    Ok((url, download))
+}
+
+#[derive(Debug, Deserialize)]
+struct PaperProjectResponse {
+    versions: Vec<String>,
+}
+
+#[derive(Debug, Deserialize)]
+struct PaperBuildsResponse {
+    builds: Vec<PaperBuild>,
+}
+
+#[derive(Debug, Deserialize)]
+struct PaperBuild {
+    build: u16,
+    time: String,
+    changes: Vec<PaperChange>,
+}
+
+#[derive(Debug, Deserialize)]
+struct PaperChange {
+    summary: String,
 }
 
 pub struct PaperJarProvider;
 impl ServerJarProvider for PaperJarProvider {
-    fn download_jar(version: &str) -> Result<Bytes> {
-        let build_id = latest_paper_build(version)?;
-        let download_url = paper_url(version, build_id)?;
+    fn resolve_build(version: &str, build: Option<&str>, _: &JarOptions) -> Result<JarBuild> {
+        let build_id = paper_build(version, build)?;
+        let (download_url, download) = paper_download(version, build_id)?;
+
+        let build = JarBuild {
+            build: build_id.to_string(),
+            file_name: download.name,
+            download_url,
+            checksum: Some(Checksum::Sha256(download.sha256)),
+        };
+
+        Ok(build)
+    }
+
+    fn versions() -> Result<Vec<String>> {
+        let url = format!("{}/projects/paper", api_urls().paper);
+        let response = CLIENT
+            .get(url)
+            .send()?
+            .error_for_status()?
+            .json::<PaperProjectResponse>()?;
+
+        Ok(response.versions)
+    }
+
+    fn builds(version: &str, limit: usize, _: &JarOptions) -> Result<Vec<BuildInfo>> {
+        let url = format!(
+            "{}/projects/paper/versions/{}/builds",
+            api_urls().paper,
+            version
+        );
+
+        let response = CLIENT
+            .get(url)
+            .send()?
+            .error_for_status()?
+            .json::<PaperBuildsResponse>()?;
+
+        let builds = response
+            .builds
+            .into_iter()
+            .rev()
+            .take(limit)
+            .map(|build| BuildInfo {
+                build: build.build.to_string(),
+                time: Some(build.time),
+                changes: build
+                    .changes
+                    .into_iter()
+                    .map(|change| change.summary)
+                    .collect(),
+            })
+            .collect();
+
+        Ok(builds)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::PaperJarProvider;
+    use crate::arg_types::JarType;
+    use crate::server_jar::{self, test_server, JarOptions, ServerJarProvider};
+
+    #[test]
+    fn test_paper_download_verified() {
+        let sha256 = server_jar::sha256_hex(b"paper jar");
+        let build = |build: u16, sha256: &str| {
+            format!(
+                r#"{{"project_id":"paper","project_name":"Paper","version":"1.18.1","build":{},"downloads":{{"application":{{"name":"paper-{}.jar","sha256":"{}"}}}}}}"#,
+                build, build, sha256
+            )
+        };
+
+        test_server::serve(
+            JarType::Paper,
+            vec![
+                (
+                    "/projects/paper/versions/1.18.1",
+                    r#"{"project_id":"paper","project_name":"Paper","version":"1.18.1","builds":[40,41,42]}"#.to_owned(),
+                ),
+                ("/projects/paper/versions/1.18.1/builds/42", build(42, &sha256)),
+                ("/projects/paper/versions/1.18.1/builds/41", build(41, "00")),
+                (
+                    "/projects/paper/versions/1.18.1/builds/40",
+                    r#"{"project_id":"paper","project_name":"Paper","version":"1.18.1","build":40,"downloads":{}}"#.to_owned(),
+                ),
+                (
+                    "/projects/paper/versions/1.18.1/builds/42/downloads/paper-42.jar",
+                    "paper jar".to_owned(),
+                ),
+                (
+                    "/projects/paper/versions/1.18.1/builds/41/downloads/paper-41.jar",
+                    "tampered jar".to_owned(),
+                ),
+            ],
+        );
+
+        let options = JarOptions::default();
+        let latest = PaperJarProvider::resolve_build("1.18.1", None, &options).unwrap();
+        assert_eq!(latest.build, "42");
+
+        let jar = server_jar::download_build(JarType::Paper, "1.18.1", &latest).unwrap();
+        assert_eq!(jar.sha256, sha256);
 
-        info!("downloading paper.jar build {} for {}", build_id, version);
-        let bytes = CLIENT.get(download_url).send()?.bytes()?;
+        let pinned = PaperJarProvider::resolve_build("1.18.1", Some("41"), &options).unwrap();
+        assert!(server_jar::download_build(JarType::Paper, "1.18.1", &pinned).is_err());
 
-        Ok(bytes)
+        assert!(PaperJarProvider::resolve_build("1.18.1", Some("40"), &options).is_err());
+        assert!(PaperJarProvider::resolve_build("1.18.1", Some("7"), &options).is_err());
+        assert!(PaperJarProvider::resolve_build("1.0.0", None, &options).is_err());
     }
 }
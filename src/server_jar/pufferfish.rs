@@ -1,11 +1,11 @@
-use bytes::Bytes;
 use color_eyre::Result;
 use reqwest::StatusCode;
 use serde::Deserialize;
-use tracing::{error, info};
 
-use super::http::CLIENT;
-use super::ServerJarProvider;
+use super::http::{api_urls, CLIENT};
+use super::{BuildInfo, JarBuild, JarOptions, ServerJarError, ServerJarProvider};
+use crate::arg_types::JarType;
+use crate::utils;
 
 #[derive(Debug, Deserialize)]
 #[allow(dead_code)]
@@ -23,51 +23,219 @@ struct PufferfishArtifact {
     relative_path: String,
 }
 
-fn get_latest_artifact_url(version: &str) -> Result<(u32, String)> {
+fn get_artifact_url(version: &str, build: Option<&str>) -> Result<(u32, String, String)> {
+    let selector = build.unwrap_or("lastSuccessfulBuild");
     let url = format!(
-        "https://ci.pufferfish.host/job/Pufferfish-{}/lastSuccessfulBuild/api/json",
-        version
+        "{}/job/Pufferfish-{}/{}/api/json",
+        api_urls().pufferfish,
+        version,
+        selector
     );
 
     let response = CLIENT.get(url).send()?;
 
     if response.status() == StatusCode::NOT_FOUND {
-        error!("unsupported pufferfish version: {}", version);
-        std::process::exit(1)
+        if let Some(build) = build {
+            return Err(ServerJarError::BuildNotFound {
+                jar_type: JarType::Pufferfish,
+                version: version.to_owned(),
+                build: build.to_owned(),
+            }
+            .into());
+        }
+
+        return Err(ServerJarError::VersionNotFound {
+            jar_type: JarType::Pufferfish,
+            version: version.to_owned(),
+        }
+        .into());
     }
 
     let response = response
         .error_for_status()?
         .json::<PufferfishArtifactResponse>()?;
 
-    let jar_artifact = match response.artifacts.get(0) {
-        None => {
-            error!("no pufferfish build artifacts found");
-            std::process::exit(1)
-        }
-
-        Some(artifact) => artifact,
-    };
+    // Failed builds are still listed, but have nothing to download
+    let jar_artifact = response
+        .artifacts
+        .first()
+        .ok_or_else(|| ServerJarError::BuildNotFound {
+            jar_type: JarType::Pufferfish,
+            version: version.to_owned(),
+            build: response.number.to_string(),
+        })?;
 
     let artifact_url = format!(
-        "https://ci.pufferfish.host/job/Pufferfish-{}/lastSuccessfulBuild/artifact/{}",
-        version, jar_artifact.relative_path
+        "{}/job/Pufferfish-{}/{}/artifact/{}",
+        api_urls().pufferfish,
+        version,
+        response.number,
+        jar_artifact.relative_path
     );
 
-    Ok((response.number, artifact_url))
+    Ok((
+        response.number,
+        jar_artifact.file_name.clone(),
+        artifact_url,
+    ))
+}
+
+#[derive(Debug, Deserialize)]
+struct JenkinsJobsResponse {
+    jobs: Vec<JenkinsJob>,
+}
+
+#[derive(Debug, Deserialize)]
+struct JenkinsJob {
+    name: String,
+}
+
+#[derive(Debug, Deserialize)]
+struct JenkinsBuildsResponse {
+    builds: Vec<JenkinsBuild>,
+}
+
+#[derive(Debug, Deserialize)]
+#[serde(rename_all = "camelCase")]
+struct JenkinsBuild {
+    number: u32,
+    timestamp: u64,
+    result: Option<String>,
+    change_set: JenkinsChangeSet,
+}
+
+#[derive(Debug, Deserialize)]
+struct JenkinsChangeSet {
+    items: Vec<JenkinsChange>,
+}
+
+#[derive(Debug, Deserialize)]
+struct JenkinsChange {
+    msg: String,
 }
 
 pub struct PufferfishJarProvider;
 impl ServerJarProvider for PufferfishJarProvider {
-    fn download_jar(version: &str) -> Result<Bytes> {
-        let (build_id, artifact_url) = get_latest_artifact_url(version)?;
+    fn resolve_build(version: &str, build: Option<&str>, _: &JarOptions) -> Result<JarBuild> {
+        let (build_id, file_name, artifact_url) = get_artifact_url(version, build)?;
+
+        // Jenkins doesn't publish checksums, so the hash is only recorded after download
+        let build = JarBuild {
+            build: build_id.to_string(),
+            file_name,
+            download_url: artifact_url,
+            checksum: None,
+        };
+
+        Ok(build)
+    }
+
+    fn versions() -> Result<Vec<String>> {
+        let url = format!("{}/api/json?tree=jobs[name]", api_urls().pufferfish);
+        let response = CLIENT
+            .get(url)
+            .send()?
+            .error_for_status()?
+            .json::<JenkinsJobsResponse>()?;
+
+        let versions = response
+            .jobs
+            .into_iter()
+            .filter_map(|job| job.name.strip_prefix("Pufferfish-").map(str::to_owned))
+            .collect();
 
-        info!(
-            "downloading pufferfish.jar build {} for {}",
-            build_id, version
+        Ok(versions)
+    }
+
+    fn builds(version: &str, limit: usize, _: &JarOptions) -> Result<Vec<BuildInfo>> {
+        let url = format!(
+            "{}/job/Pufferfish-{}/api/json?tree=builds[number,timestamp,result,changeSet[items[msg]]]",
+            api_urls().pufferfish,
+            version
         );
 
-        let bytes = CLIENT.get(artifact_url).send()?.bytes()?;
-        Ok(bytes)
+        let response = CLIENT.get(url).send()?;
+        if response.status() == StatusCode::NOT_FOUND {
+            return Err(ServerJarError::VersionNotFound {
+                jar_type: JarType::Pufferfish,
+                version: version.to_owned(),
+            }
+            .into());
+        }
+
+        let response = response
+            .error_for_status()?
+            .json::<JenkinsBuildsResponse>()?;
+
+        // Only successful builds have artifacts to download
+        let builds = response
+            .builds
+            .into_iter()
+            .filter(|build| build.result.as_deref() == Some("SUCCESS"))
+            .take(limit)
+            .map(|build| BuildInfo {
+                build: build.number.to_string(),
+                time: Some(utils::format_unix_timestamp(build.timestamp / 1000)),
+                changes: build
+                    .change_set
+                    .items
+                    .into_iter()
+                    .map(|change| change.msg)
+                    .collect(),
+            })
+            .collect();
+
+        Ok(builds)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::PufferfishJarProvider;
+    use crate::arg_types::JarType;
+    use crate::server_jar::{self, test_server, JarOptions, ServerJarProvider};
+
+    #[test]
+    fn test_pufferfish_download() {
+        let artifact = |build: u32, artifacts: &str| {
+            format!(r#"{{"number":{},"artifacts":[{}]}}"#, build, artifacts)
+        };
+
+        let jar_artifact = r#"{"displayPath":"pufferfish-paperclip.jar","fileName":"pufferfish-paperclip.jar","relativePath":"build/libs/pufferfish-paperclip.jar"}"#;
+
+        test_server::serve(
+            JarType::Pufferfish,
+            vec![
+                (
+                    "/job/Pufferfish-1.18/lastSuccessfulBuild/api/json",
+                    artifact(52, jar_artifact),
+                ),
+                (
+                    "/job/Pufferfish-1.18/50/api/json",
+                    artifact(50, jar_artifact),
+                ),
+                // Failed builds are still listed, without artifacts
+                ("/job/Pufferfish-1.18/51/api/json", artifact(51, "")),
+                (
+                    "/job/Pufferfish-1.18/52/artifact/build/libs/pufferfish-paperclip.jar",
+                    "pufferfish jar".to_owned(),
+                ),
+            ],
+        );
+
+        let options = JarOptions::default();
+        let latest = PufferfishJarProvider::resolve_build("1.18", None, &options).unwrap();
+        assert_eq!(latest.build, "52");
+        assert_eq!(latest.file_name, "pufferfish-paperclip.jar");
+
+        let jar = server_jar::download_build(JarType::Pufferfish, "1.18", &latest).unwrap();
+        assert_eq!(jar.sha256, server_jar::sha256_hex(b"pufferfish jar"));
+
+        let pinned = PufferfishJarProvider::resolve_build("1.18", Some("50"), &options).unwrap();
+        assert_eq!(pinned.build, "50");
+
+        assert!(PufferfishJarProvider::resolve_build("1.18", Some("51"), &options).is_err());
+        assert!(PufferfishJarProvider::resolve_build("1.18", Some("7"), &options).is_err());
+        assert!(PufferfishJarProvider::resolve_build("1.0", None, &options).is_err());
     }
 }
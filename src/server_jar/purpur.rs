@@ -0,0 +1,203 @@
+use color_eyre::Result;
+use serde::Deserialize;
+
+use super::http::{api_urls, CLIENT};
+use super::{BuildInfo, Checksum, JarBuild, JarOptions, ServerJarError, ServerJarProvider};
+use crate::arg_types::JarType;
+use crate::utils;
+
+#[derive(Debug, Deserialize)]
+#[allow(dead_code)]
+struct PurpurVersionResponse {
+    project: String,
+    version: String,
+    builds: PurpurBuilds,
+}
+
+#[derive(Debug, Deserialize)]
+struct PurpurBuilds {
+    latest: String,
+    all: Vec<String>,
+}
+
+fn purpur_build(version: &str, build: Option<&str>) -> Result<String> {
+    let url = format!("{}/purpur/{}", api_urls().purpur, version);
+
+    let response = CLIENT
+        .get(url)
+        .send()?
+        .error_for_status()?
+        .json::<PurpurVersionResponse>()?;
+
+    let build_id = match build {
+        None => response.builds.latest,
+        Some(build) if response.builds.all.iter().any(|id| id == build) => build.to_owned(),
+        Some(build) => {
+            return Err(ServerJarError::BuildNotFound {
+                jar_type: JarType::Purpur,
+                version: version.to_owned(),
+                build: build.to_owned(),
+            }
+            .into())
+        }
+    };
+
+    Ok(build_id)
+}
+
+#[derive(Debug, Deserialize)]
+#[allow(dead_code)]
+struct PurpurBuildResponse {
+    project: String,
+    version: String,
+    build: String,
+    result: String,
+    md5: Option<String>,
+    timestamp: Option<u64>,
+    #[serde(default)]
+    commits: Vec<PurpurCommit>,
+}
+
+#[derive(Debug, Deserialize)]
+struct PurpurCommit {
+    description: String,
+}
+
+#[derive(Debug, Deserialize)]
+struct PurpurProjectResponse {
+    versions: Vec<String>,
+}
+
+fn purpur_build_info(version: &str, build_id: &str) -> Result<PurpurBuildResponse> {
+    let url = format!("{}/purpur/{}/{}", api_urls().purpur, version, build_id);
+    let response = CLIENT
+        .get(url)
+        .send()?
+        .error_for_status()?
+        .json::<PurpurBuildResponse>()?;
+
+    Ok(response)
+}
+
+pub struct PurpurJarProvider;
+impl ServerJarProvider for PurpurJarProvider {
+    fn resolve_build(version: &str, build: Option<&str>, _: &JarOptions) -> Result<JarBuild> {
+        let build_id = purpur_build(version, build)?;
+
+        let response = purpur_build_info(version, &build_id)?;
+
+        // Failed builds are listed, but have nothing to download
+        if response.result != "SUCCESS" {
+            return Err(ServerJarError::BuildNotFound {
+                jar_type: JarType::Purpur,
+                version: version.to_owned(),
+                build: build_id,
+            }
+            .into());
+        }
+
+        let download_url = format!(
+            "{}/purpur/{}/{}/download",
+            api_urls().purpur,
+            version,
+            build_id
+        );
+
+        let build = JarBuild {
+            file_name: format!("purpur-{}-{}.jar", version, build_id),
+            build: build_id,
+            download_url,
+            checksum: response.md5.map(Checksum::Md5),
+        };
+
+        Ok(build)
+    }
+
+    fn versions() -> Result<Vec<String>> {
+        let url = format!("{}/purpur", api_urls().purpur);
+        let response = CLIENT
+            .get(url)
+            .send()?
+            .error_for_status()?
+            .json::<PurpurProjectResponse>()?;
+
+        Ok(response.versions)
+    }
+
+    fn builds(version: &str, limit: usize, _: &JarOptions) -> Result<Vec<BuildInfo>> {
+        let url = format!("{}/purpur/{}", api_urls().purpur, version);
+        let response = CLIENT
+            .get(url)
+            .send()?
+            .error_for_status()?
+            .json::<PurpurVersionResponse>()?;
+
+        // Build details (and changelogs) need one request per build
+        let mut builds = Vec::with_capacity(limit);
+        for build_id in response.builds.all.iter().rev().take(limit) {
+            let build = purpur_build_info(version, build_id)?;
+            builds.push(BuildInfo {
+                build: build.build,
+                time: build
+                    .timestamp
+                    .map(|timestamp| utils::format_unix_timestamp(timestamp / 1000)),
+                changes: build
+                    .commits
+                    .into_iter()
+                    .map(|commit| commit.description)
+                    .collect(),
+            });
+        }
+
+        Ok(builds)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use md5::{Digest, Md5};
+
+    use super::PurpurJarProvider;
+    use crate::arg_types::JarType;
+    use crate::server_jar::{self, test_server, JarOptions, ServerJarProvider};
+
+    #[test]
+    fn test_purpur_download_verified() {
+        let md5 = server_jar::to_hex(&Md5::digest(b"purpur jar"));
+        let build = |build: u16, result: &str, md5: &str| {
+            format!(
+                r#"{{"project":"purpur","version":"1.18.1","build":"{}","result":"{}","md5":"{}"}}"#,
+                build, result, md5
+            )
+        };
+
+        test_server::serve(
+            JarType::Purpur,
+            vec![
+                (
+                    "/purpur/1.18.1",
+                    r#"{"project":"purpur","version":"1.18.1","builds":{"latest":"1500","all":["1498","1499","1500"]}}"#.to_owned(),
+                ),
+                ("/purpur/1.18.1/1500", build(1500, "SUCCESS", &md5)),
+                ("/purpur/1.18.1/1499", build(1499, "SUCCESS", "00")),
+                ("/purpur/1.18.1/1498", build(1498, "FAILURE", "")),
+                ("/purpur/1.18.1/1500/download", "purpur jar".to_owned()),
+                ("/purpur/1.18.1/1499/download", "tampered jar".to_owned()),
+            ],
+        );
+
+        let options = JarOptions::default();
+        let latest = PurpurJarProvider::resolve_build("1.18.1", None, &options).unwrap();
+        assert_eq!(latest.build, "1500");
+        assert_eq!(latest.file_name, "purpur-1.18.1-1500.jar");
+
+        let jar = server_jar::download_build(JarType::Purpur, "1.18.1", &latest).unwrap();
+        assert_eq!(jar.sha256, server_jar::sha256_hex(b"purpur jar"));
+
+        let pinned = PurpurJarProvider::resolve_build("1.18.1", Some("1499"), &options).unwrap();
+        assert!(server_jar::download_build(JarType::Purpur, "1.18.1", &pinned).is_err());
+
+        assert!(PurpurJarProvider::resolve_build("1.18.1", Some("1498"), &options).is_err());
+        assert!(PurpurJarProvider::resolve_build("1.18.1", Some("7"), &options).is_err());
+    }
+}
@@ -0,0 +1,86 @@
+use std::collections::HashMap;
+use std::io::{BufRead, BufReader, Write};
+use std::net::TcpListener;
+use std::sync::Mutex;
+use std::thread;
+
+use once_cell::sync::Lazy;
+
+use super::http::{self, ApiUrls, HttpOptions};
+use crate::arg_types::JarType;
+
+/// Response bodies by request path
+static ROUTES: Lazy<Mutex<HashMap<String, Vec<u8>>>> = Lazy::new(Default::default);
+
+/// Address of the stand-in server, which every provider's API URL points to
+///
+/// The HTTP client can only be configured once per process, so all tests share one server and
+/// each provider is served under its own path prefix
+static ADDRESS: Lazy<String> = Lazy::new(|| {
+    let listener = TcpListener::bind("127.0.0.1:0").unwrap();
+    let address = format!("http://{}", listener.local_addr().unwrap());
+
+    thread::spawn(move || {
+        for stream in listener.incoming() {
+            let mut stream = stream.unwrap();
+            let mut reader = BufReader::new(stream.try_clone().unwrap());
+
+            let mut request_line = String::new();
+            reader.read_line(&mut request_line).unwrap();
+            let path = request_line.split(' ').nth(1).unwrap_or_default();
+
+            // Skip headers
+            let mut line = String::new();
+            while reader.read_line(&mut line).unwrap() > 2 {
+                line.clear();
+            }
+
+            let response = match ROUTES.lock().unwrap().get(path) {
+                Some(body) => {
+                    let mut response = format!(
+                        "HTTP/1.1 200 OK\r\nContent-Length: {}\r\nConnection: close\r\n\r\n",
+                        body.len()
+                    )
+                    .into_bytes();
+
+                    response.extend(body);
+                    response
+                }
+
+                None => b"HTTP/1.1 404 Not Found\r\nContent-Length: 0\r\nConnection: close\r\n\r\n"
+                    .to_vec(),
+            };
+
+            stream.write_all(&response).unwrap();
+        }
+    });
+
+    let provider_url = |jar_type: JarType| format!("{}/{}", address, jar_type);
+    http::configure(HttpOptions {
+        api_urls: ApiUrls {
+            paper: provider_url(JarType::Paper),
+            pufferfish: provider_url(JarType::Pufferfish),
+            purpur: provider_url(JarType::Purpur),
+            vanilla: provider_url(JarType::Vanilla),
+            fabric: provider_url(JarType::Fabric),
+        },
+        ..Default::default()
+    })
+    .unwrap();
+
+    address
+});
+
+/// Serves fixed responses for a provider, by path relative to its API URL
+///
+/// Returns the provider's API URL, for responses that link back to the server
+pub fn serve(jar_type: JarType, routes: Vec<(&str, impl Into<Vec<u8>>)>) -> String {
+    let base_url = format!("{}/{}", *ADDRESS, jar_type);
+
+    let mut served = ROUTES.lock().unwrap();
+    for (path, body) in routes {
+        served.insert(format!("/{}{}", jar_type, path), body.into());
+    }
+
+    base_url
+}
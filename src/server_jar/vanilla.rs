@@ -0,0 +1,235 @@
+use color_eyre::Result;
+use serde::Deserialize;
+
+use super::http::{api_urls, ApiUrls, CLIENT};
+use super::{BuildInfo, Checksum, JarBuild, JarOptions, ServerJarError, ServerJarProvider};
+use crate::arg_types::JarType;
+
+/// Hosts the version manifest links to, which are fetched through the configured URL instead
+const MOJANG_HOSTS: [&str; 4] = [
+    "https://launchermeta.mojang.com",
+    "https://launcher.mojang.com",
+    "https://piston-meta.mojang.com",
+    "https://piston-data.mojang.com",
+];
+
+/// Rewrites a link from the version manifest to use the configured URL, as mirrors serve the
+/// paths from every Mojang host under one base URL
+fn mirror_url(url: &str) -> String {
+    let base = &api_urls().vanilla;
+    if *base == ApiUrls::default().vanilla {
+        return url.to_owned();
+    }
+
+    MOJANG_HOSTS
+        .iter()
+        .find_map(|host| url.strip_prefix(host))
+        .map_or_else(|| url.to_owned(), |path| format!("{}{}", base, path))
+}
+
+#[derive(Debug, Deserialize)]
+#[allow(dead_code)]
+struct VersionManifest {
+    latest: LatestVersions,
+    versions: Vec<ManifestVersion>,
+}
+
+#[derive(Debug, Deserialize)]
+#[allow(dead_code)]
+struct LatestVersions {
+    release: String,
+    snapshot: String,
+}
+
+#[derive(Debug, Deserialize)]
+#[allow(dead_code)]
+#[serde(rename_all = "camelCase")]
+struct ManifestVersion {
+    id: String,
+    #[serde(rename = "type")]
+    version_type: String,
+    url: String,
+    release_time: String,
+    sha1: String,
+}
+
+fn version_manifest() -> Result<VersionManifest> {
+    let url = format!("{}/mc/game/version_manifest_v2.json", api_urls().vanilla);
+    let response = CLIENT
+        .get(url)
+        .send()?
+        .error_for_status()?
+        .json::<VersionManifest>()?;
+
+    Ok(response)
+}
+
+fn manifest_version(version: &str) -> Result<ManifestVersion> {
+    let version = version_manifest()?
+        .versions
+        .into_iter()
+        .find(|manifest_version| manifest_version.id == version)
+        .ok_or_else(|| ServerJarError::VersionNotFound {
+            jar_type: JarType::Vanilla,
+            version: version.to_owned(),
+        })?;
+
+    Ok(version)
+}
+
+#[derive(Debug, Deserialize)]
+struct VersionResponse {
+    downloads: VersionDownloads,
+}
+
+#[derive(Debug, Deserialize)]
+struct VersionDownloads {
+    server: Option<VersionDownload>,
+}
+
+#[derive(Debug, Deserialize)]
+#[allow(dead_code)]
+struct VersionDownload {
+    sha1: String,
+    size: u64,
+    url: String,
+}
+
+fn server_download(version: &str) -> Result<(ManifestVersion, VersionDownload)> {
+    let manifest_version = manifest_version(version)?;
+    let response = CLIENT
+        .get(mirror_url(&manifest_version.url))
+        .send()?
+        .error_for_status()?
+        .json::<VersionResponse>()?;
+
+    // Very old versions never had a server download
+    match response.downloads.server {
+        Some(download) => Ok((manifest_version, download)),
+        None => Err(ServerJarError::VersionNotFound {
+            jar_type: JarType::Vanilla,
+            version: version.to_owned(),
+        }
+        .into()),
+    }
+}
+
+/// Each version only has a single build, identified by its hash
+fn build_id(download: &VersionDownload) -> String {
+    download.sha1.get(..8).unwrap_or(&download.sha1).to_owned()
+}
+
+pub struct VanillaJarProvider;
+impl ServerJarProvider for VanillaJarProvider {
+    fn resolve_build(version: &str, build: Option<&str>, _: &JarOptions) -> Result<JarBuild> {
+        let (_, download) = server_download(version)?;
+
+        let build_id = build_id(&download);
+        if let Some(build) = build {
+            if build != build_id {
+                return Err(ServerJarError::BuildNotFound {
+                    jar_type: JarType::Vanilla,
+                    version: version.to_owned(),
+                    build: build.to_owned(),
+                }
+                .into());
+            }
+        }
+
+        let build = JarBuild {
+            build: build_id,
+            file_name: format!("minecraft_server.{}.jar", version),
+            download_url: mirror_url(&download.url),
+            checksum: Some(Checksum::Sha1(download.sha1)),
+        };
+
+        Ok(build)
+    }
+
+    /// Only lists releases, snapshots can still be used but would drown out everything else
+    fn versions() -> Result<Vec<String>> {
+        let versions = version_manifest()?
+            .versions
+            .into_iter()
+            .filter(|version| version.version_type == "release")
+            .map(|version| version.id)
+            .rev()
+            .collect();
+
+        Ok(versions)
+    }
+
+    fn builds(version: &str, _: usize, _: &JarOptions) -> Result<Vec<BuildInfo>> {
+        let (manifest_version, download) = server_download(version)?;
+        let build = BuildInfo {
+            build: build_id(&download),
+            time: Some(manifest_version.release_time),
+            changes: vec![],
+        };
+
+        Ok(vec![build])
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use sha1::{Digest, Sha1};
+
+    use super::VanillaJarProvider;
+    use crate::arg_types::JarType;
+    use crate::server_jar::{self, test_server, JarOptions, ServerJarProvider};
+
+    #[test]
+    fn test_vanilla_download_verified() {
+        let sha1 = server_jar::to_hex(&Sha1::digest(b"vanilla jar"));
+        let manifest_version = |id: &str| {
+            format!(
+                r#"{{"id":"{0}","type":"release","url":"https://piston-meta.mojang.com/v1/packages/{0}.json","releaseTime":"2021-12-10T08:23:00+00:00","sha1":""}}"#,
+                id
+            )
+        };
+
+        let version = |id: &str, sha1: &str| {
+            format!(
+                r#"{{"downloads":{{"server":{{"sha1":"{}","size":11,"url":"https://piston-data.mojang.com/v1/objects/{}/server.jar"}}}}}}"#,
+                sha1, id
+            )
+        };
+
+        test_server::serve(
+            JarType::Vanilla,
+            vec![
+                (
+                    "/mc/game/version_manifest_v2.json",
+                    format!(
+                        r#"{{"latest":{{"release":"1.18.1","snapshot":"1.18.1"}},"versions":[{},{},{}]}}"#,
+                        manifest_version("1.18.1"),
+                        manifest_version("1.18"),
+                        manifest_version("1.2.5"),
+                    ),
+                ),
+                ("/v1/packages/1.18.1.json", version("1.18.1", &sha1)),
+                ("/v1/packages/1.18.json", version("1.18", "0000")),
+                ("/v1/packages/1.2.5.json", r#"{"downloads":{}}"#.to_owned()),
+                ("/v1/objects/1.18.1/server.jar", "vanilla jar".to_owned()),
+                ("/v1/objects/1.18/server.jar", "tampered jar".to_owned()),
+            ],
+        );
+
+        let options = JarOptions::default();
+        let latest = VanillaJarProvider::resolve_build("1.18.1", None, &options).unwrap();
+        assert_eq!(latest.build, &sha1[..8]);
+
+        let jar = server_jar::download_build(JarType::Vanilla, "1.18.1", &latest).unwrap();
+        assert_eq!(jar.sha256, server_jar::sha256_hex(b"vanilla jar"));
+
+        // Short hashes are used as the build id whole
+        let mismatched = VanillaJarProvider::resolve_build("1.18", None, &options).unwrap();
+        assert_eq!(mismatched.build, "0000");
+        assert!(server_jar::download_build(JarType::Vanilla, "1.18", &mismatched).is_err());
+
+        assert!(VanillaJarProvider::resolve_build("1.18.1", Some("0000"), &options).is_err());
+        assert!(VanillaJarProvider::resolve_build("1.2.5", None, &options).is_err());
+        assert!(VanillaJarProvider::resolve_build("1.0", None, &options).is_err());
+    }
+}
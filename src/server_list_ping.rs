@@ -0,0 +1,243 @@
+use std::io::{self, Read, Write};
+use std::net::{Ipv4Addr, SocketAddr, TcpStream};
+use std::thread;
+use std::time::{Duration, Instant};
+
+use serde::Deserialize;
+use serde_json::Value;
+use thiserror::Error;
+
+/// Sent in the handshake when we don't care which protocol version the server speaks
+const ANY_PROTOCOL: i32 = -1;
+
+/// Handshake state that requests a status response rather than logging in
+const NEXT_STATE_STATUS: i32 = 1;
+
+/// Response to a Server List Ping, as shown in the client's server list
+#[derive(Debug, Clone, Deserialize)]
+pub struct PingStatus {
+    pub version: PingVersion,
+    pub players: PingPlayers,
+    #[serde(default)]
+    pub description: Value,
+}
+
+#[derive(Debug, Clone, Deserialize)]
+pub struct PingVersion {
+    pub name: String,
+    pub protocol: i32,
+}
+
+#[derive(Debug, Clone, Deserialize)]
+pub struct PingPlayers {
+    pub max: i32,
+    pub online: i32,
+}
+
+impl PingStatus {
+    /// The MOTD as plain text, without any formatting
+    pub fn motd(&self) -> String {
+        let mut motd = String::new();
+        flatten_text(&self.description, &mut motd);
+
+        motd
+    }
+}
+
+/// Descriptions are either a plain string or a chat component with nested `extra` components
+fn flatten_text(component: &Value, out: &mut String) {
+    match component {
+        Value::String(text) => out.push_str(text),
+        Value::Array(components) => components.iter().for_each(|c| flatten_text(c, out)),
+        Value::Object(object) => {
+            if let Some(text) = object.get("text") {
+                flatten_text(text, out);
+            }
+
+            if let Some(extra) = object.get("extra") {
+                flatten_text(extra, out);
+            }
+        }
+
+        _ => (),
+    }
+}
+
+#[derive(Debug, Error)]
+pub enum PingError {
+    #[error(transparent)]
+    Io(#[from] io::Error),
+
+    #[error("varint is longer than 5 bytes")]
+    VarIntTooLong,
+
+    #[error("expected status response packet, got packet id {0:#04x}")]
+    UnexpectedPacket(i32),
+
+    #[error("invalid status response: {0}")]
+    InvalidResponse(#[from] serde_json::Error),
+}
+
+// region: Framing
+fn write_varint(buf: &mut Vec<u8>, value: i32) {
+    // Negative values are sent as their two's complement, so always take 5 bytes
+    let mut value = value as u32;
+    loop {
+        let byte = (value & 0x7f) as u8;
+        value >>= 7;
+
+        if value == 0 {
+            buf.push(byte);
+            return;
+        }
+
+        buf.push(byte | 0x80);
+    }
+}
+
+fn read_varint<R: Read>(reader: &mut R) -> Result<i32, PingError> {
+    let mut value = 0_u32;
+    for i in 0..5 {
+        let mut byte = [0];
+        reader.read_exact(&mut byte)?;
+
+        value |= u32::from(byte[0] & 0x7f) << (7 * i);
+        if byte[0] & 0x80 == 0 {
+            return Ok(value as i32);
+        }
+    }
+
+    Err(PingError::VarIntTooLong)
+}
+
+fn write_string(buf: &mut Vec<u8>, value: &str) {
+    write_varint(buf, value.len() as i32);
+    buf.extend_from_slice(value.as_bytes());
+}
+
+/// Prefixes a packet with its length
+fn write_packet<W: Write>(writer: &mut W, packet: &[u8]) -> io::Result<()> {
+    let mut framed = Vec::with_capacity(packet.len() + 5);
+    write_varint(&mut framed, packet.len() as i32);
+    framed.extend_from_slice(packet);
+
+    writer.write_all(&framed)
+}
+
+fn read_packet<R: Read>(reader: &mut R) -> Result<(i32, Vec<u8>), PingError> {
+    let length = read_varint(reader)?;
+    let mut packet = vec![0; length.max(0) as usize];
+    reader.read_exact(&mut packet)?;
+
+    let mut body = packet.as_slice();
+    let id = read_varint(&mut body)?;
+
+    Ok((id, body.to_vec()))
+}
+// endregion
+
+/// Sends a handshake and status request, and parses the server's status response
+pub fn ping(addr: SocketAddr, timeout: Duration) -> Result<PingStatus, PingError> {
+    let mut stream = TcpStream::connect_timeout(&addr, timeout)?;
+    stream.set_read_timeout(Some(timeout))?;
+    stream.set_write_timeout(Some(timeout))?;
+
+    let mut handshake = vec![0x00];
+    write_varint(&mut handshake, ANY_PROTOCOL);
+    write_string(&mut handshake, &addr.ip().to_string());
+    handshake.extend_from_slice(&addr.port().to_be_bytes());
+    write_varint(&mut handshake, NEXT_STATE_STATUS);
+
+    write_packet(&mut stream, &handshake)?;
+    write_packet(&mut stream, &[0x00])?;
+
+    let (id, packet) = read_packet(&mut stream)?;
+    if id != 0x00 {
+        return Err(PingError::UnexpectedPacket(id));
+    }
+
+    // The response is a single length-prefixed JSON string
+    let mut body = packet.as_slice();
+    let length = read_varint(&mut body)?.max(0) as usize;
+    let json = &body[..length.min(body.len())];
+
+    let status = serde_json::from_slice(json)?;
+    Ok(status)
+}
+
+/// Pings a local server's port
+pub fn ping_local(port: u16, timeout: Duration) -> Result<PingStatus, PingError> {
+    ping(SocketAddr::from((Ipv4Addr::LOCALHOST, port)), timeout)
+}
+
+/// Pings a local server until it responds, returns `None` on timeout
+pub fn wait_until_ready(port: u16, timeout: Duration) -> Option<PingStatus> {
+    let deadline = Instant::now() + timeout;
+
+    while Instant::now() < deadline {
+        if let Ok(status) = ping_local(port, Duration::from_secs(2)) {
+            return Some(status);
+        }
+
+        thread::sleep(Duration::from_secs(1));
+    }
+
+    None
+}
+
+#[cfg(test)]
+mod tests {
+    use std::net::TcpListener;
+
+    use super::*;
+
+    #[test]
+    fn test_varint_round_trip() {
+        for value in [0, 1, 127, 128, 255, 25565, 2097151, i32::MAX, -1, i32::MIN] {
+            let mut buf = vec![];
+            write_varint(&mut buf, value);
+
+            assert!(buf.len() <= 5);
+            assert_eq!(read_varint(&mut buf.as_slice()).unwrap(), value);
+        }
+
+        let mut buf = vec![];
+        write_varint(&mut buf, 300);
+        assert_eq!(buf, [0xac, 0x02]);
+    }
+
+    #[test]
+    fn test_ping_fake_server() {
+        let listener = TcpListener::bind("127.0.0.1:0").unwrap();
+        let addr = listener.local_addr().unwrap();
+
+        let server = thread::spawn(move || {
+            let (mut stream, _) = listener.accept().unwrap();
+
+            // Handshake, then an empty status request
+            let (id, handshake) = read_packet(&mut stream).unwrap();
+            assert_eq!(id, 0x00);
+            assert_eq!(handshake.last(), Some(&(NEXT_STATE_STATUS as u8)));
+            assert_eq!(read_packet(&mut stream).unwrap(), (0x00, vec![]));
+
+            let json = r#"{
+                "version": {"name": "1.18.1", "protocol": 757},
+                "players": {"max": 20, "online": 3},
+                "description": {"text": "Mammoth ", "extra": [{"text": "Server 1"}]}
+            }"#;
+
+            let mut response = vec![0x00];
+            write_string(&mut response, json);
+            write_packet(&mut stream, &response).unwrap();
+        });
+
+        let status = ping(addr, Duration::from_secs(5)).unwrap();
+        server.join().unwrap();
+
+        assert_eq!(status.version.name, "1.18.1");
+        assert_eq!(status.version.protocol, 757);
+        assert_eq!(status.players.online, 3);
+        assert_eq!(status.players.max, 20);
+        assert_eq!(status.motd(), "Mammoth Server 1");
+    }
+}
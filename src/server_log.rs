@@ -0,0 +1,311 @@
+use std::fs::{self, File};
+use std::io::{self, Read, Seek, SeekFrom};
+use std::os::unix::fs::MetadataExt;
+use std::path::{Path, PathBuf};
+use std::thread;
+use std::time::{Duration, Instant, UNIX_EPOCH};
+
+use flate2::read::GzDecoder;
+use regex::Regex;
+
+use crate::utils;
+
+const LOG_DIR: &str = "logs";
+const LATEST_LOG: &str = "logs/latest.log";
+
+const DAY_SECS: u64 = 86400;
+
+/// A log line, with roughly when it was written as seconds since the unix epoch
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct TimedLine {
+    pub time: u64,
+    pub line: String,
+}
+
+/// A position in a server's `latest.log`, which is replaced with a new file on every start
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct LogPosition {
+    inode: u64,
+    offset: u64,
+}
+
+impl LogPosition {
+    /// The end of the server's current log, `None` if it hasn't written one yet
+    pub fn end_of(directory: &Path) -> Option<Self> {
+        let metadata = directory.join(LATEST_LOG).metadata().ok()?;
+
+        Some(Self {
+            inode: metadata.ino(),
+            offset: metadata.len(),
+        })
+    }
+}
+
+/// Reads complete lines written since `since`, from the start of the log if it has been replaced
+///
+/// Returns the new lines along with the position to continue reading from
+pub fn read_since(
+    directory: &Path,
+    since: Option<LogPosition>,
+) -> io::Result<(Vec<String>, Option<LogPosition>)> {
+    let mut file = match File::open(directory.join(LATEST_LOG)) {
+        Ok(file) => file,
+        Err(error) if error.kind() == io::ErrorKind::NotFound => return Ok((vec![], since)),
+        Err(error) => return Err(error),
+    };
+
+    let metadata = file.metadata()?;
+    let offset = match since {
+        Some(since) if since.inode == metadata.ino() && since.offset <= metadata.len() => {
+            since.offset
+        }
+
+        _ => 0,
+    };
+
+    file.seek(SeekFrom::Start(offset))?;
+    let mut bytes = vec![];
+    file.read_to_end(&mut bytes)?;
+
+    // Leave partially written lines for the next read
+    let complete = bytes
+        .iter()
+        .rposition(|b| *b == b'\n')
+        .map_or(0, |idx| idx + 1);
+    let lines = String::from_utf8_lossy(&bytes[..complete])
+        .lines()
+        .map(str::to_owned)
+        .collect();
+
+    let position = LogPosition {
+        inode: metadata.ino(),
+        offset: offset + complete as u64,
+    };
+
+    Ok((lines, Some(position)))
+}
+
+/// The last `count` lines of the server's log, empty if it hasn't written one
+pub fn tail(directory: &Path, count: usize) -> io::Result<Vec<String>> {
+    let (lines, _) = read_since(directory, None)?;
+    let skip = lines.len().saturating_sub(count);
+
+    Ok(lines.into_iter().skip(skip).collect())
+}
+
+/// Every line in the server's current log, and its rotated logs first if `archived` is set
+///
+/// Also returns the position to follow the current log from
+pub fn read_all(
+    directory: &Path,
+    archived: bool,
+) -> io::Result<(Vec<TimedLine>, Option<LogPosition>)> {
+    let mut lines = vec![];
+    if archived {
+        for (path, last_day) in archived_logs(directory)? {
+            let mut bytes = vec![];
+            GzDecoder::new(File::open(path)?).read_to_end(&mut bytes)?;
+
+            let archived_lines = String::from_utf8_lossy(&bytes)
+                .lines()
+                .map(str::to_owned)
+                .collect();
+            lines.extend(timed_lines(archived_lines, last_day));
+        }
+    }
+
+    let last_day = match directory.join(LATEST_LOG).metadata() {
+        Ok(metadata) => {
+            metadata
+                .modified()?
+                .duration_since(UNIX_EPOCH)
+                .unwrap()
+                .as_secs()
+                / DAY_SECS
+        }
+        Err(error) if error.kind() == io::ErrorKind::NotFound => return Ok((lines, None)),
+        Err(error) => return Err(error),
+    };
+
+    let (latest_lines, position) = read_since(directory, None)?;
+    lines.extend(timed_lines(latest_lines, last_day));
+
+    Ok((lines, position))
+}
+
+/// Rotated logs such as `logs/2022-01-31-1.log.gz`, oldest first, with the day each was last
+/// written to
+fn archived_logs(directory: &Path) -> io::Result<Vec<(PathBuf, u64)>> {
+    let entries = match fs::read_dir(directory.join(LOG_DIR)) {
+        Ok(entries) => entries,
+        Err(error) if error.kind() == io::ErrorKind::NotFound => return Ok(vec![]),
+        Err(error) => return Err(error),
+    };
+
+    let mut logs = vec![];
+    for entry in entries {
+        let path = entry?.path();
+        let name = path.file_name().unwrap().to_string_lossy();
+        if let Some((day, number)) = archived_log_day(&name) {
+            logs.push((day, number, path));
+        }
+    }
+
+    logs.sort();
+    Ok(logs.into_iter().map(|(day, _, path)| (path, day)).collect())
+}
+
+/// Day and number of the day's log from a name like `2022-01-31-1.log.gz`
+fn archived_log_day(name: &str) -> Option<(u64, u32)> {
+    let name = name.strip_suffix(".log.gz")?;
+    let mut parts = name.split('-').map(str::parse::<i64>);
+    let (year, month, day, number) = (
+        parts.next()?.ok()?,
+        parts.next()?.ok()?,
+        parts.next()?.ok()?,
+        parts.next()?.ok()?,
+    );
+
+    let day = utils::days_from_civil(year, month, day);
+    Some((u64::try_from(day).ok()?, u32::try_from(number).ok()?))
+}
+
+/// Seconds into the day from a line starting with a `[HH:MM:SS` timestamp
+fn time_of_day(line: &str) -> Option<u64> {
+    let time = line.strip_prefix('[')?.get(..8)?;
+    let mut parts = time.split(':').map(str::parse::<u64>);
+    let (hours, mins, secs) = (
+        parts.next()?.ok()?,
+        parts.next()?.ok()?,
+        parts.next()?.ok()?,
+    );
+
+    Some(hours * 3600 + mins * 60 + secs)
+}
+
+/// Dates lines from a log whose last line was written on `last_day`, days since the unix epoch
+///
+/// Log lines only have the time of day, so a jump back of more than 12 hours is taken as
+/// midnight passing. Lines without a timestamp, such as stack traces, share the previous time
+fn timed_lines(lines: Vec<String>, last_day: u64) -> Vec<TimedLine> {
+    let mut day_start = 0;
+    let mut previous = 0;
+
+    let mut timed = lines
+        .into_iter()
+        .map(|line| {
+            if let Some(time) = time_of_day(&line) {
+                if time + DAY_SECS / 2 < previous {
+                    day_start += DAY_SECS;
+                }
+
+                previous = time;
+            }
+
+            TimedLine {
+                time: day_start + previous,
+                line,
+            }
+        })
+        .collect::<Vec<_>>();
+
+    let first_day = last_day.saturating_sub(day_start / DAY_SECS);
+    for line in &mut timed {
+        line.time += first_day * DAY_SECS;
+    }
+
+    timed
+}
+
+/// Polls the server's log until a line written since `since` matches, returns `false` on timeout
+pub fn wait_for_line(
+    directory: &Path,
+    since: Option<LogPosition>,
+    pattern: &Regex,
+    timeout: Duration,
+) -> bool {
+    let deadline = Instant::now() + timeout;
+    let mut position = since;
+
+    while Instant::now() < deadline {
+        if let Ok((lines, next)) = read_since(directory, position) {
+            if lines.iter().any(|line| pattern.is_match(line)) {
+                return true;
+            }
+
+            position = next;
+        }
+
+        thread::sleep(Duration::from_secs(1));
+    }
+
+    false
+}
+
+#[cfg(test)]
+mod tests {
+    use std::fs::{self, OpenOptions};
+    use std::io::Write;
+
+    use super::*;
+
+    #[test]
+    fn test_read_since_rotation() {
+        let root = std::env::temp_dir().join(format!("provisioner_log_{}", std::process::id()));
+        let log_path = root.join(LATEST_LOG);
+        fs::create_dir_all(log_path.parent().unwrap()).unwrap();
+        fs::write(&log_path, "old line\n").unwrap();
+
+        let since = LogPosition::end_of(&root);
+
+        // Partial lines are held back until they're complete
+        let mut log = OpenOptions::new().append(true).open(&log_path).unwrap();
+        log.write_all(b"first\nsecond").unwrap();
+        let (lines, since) = read_since(&root, since).unwrap();
+        assert_eq!(lines, ["first"]);
+
+        log.write_all(b" line\n").unwrap();
+        let (lines, since) = read_since(&root, since).unwrap();
+        assert_eq!(lines, ["second line"]);
+
+        // A new log replaces the old file on startup
+        let new_log_path = root.join("logs/new.log");
+        fs::write(&new_log_path, "Done (2.0s)! For help, type \"help\"\n").unwrap();
+        fs::rename(&new_log_path, &log_path).unwrap();
+        let pattern = Regex::new(r"Done \([0-9.,]+s\)! For help").unwrap();
+        assert!(wait_for_line(
+            &root,
+            since,
+            &pattern,
+            Duration::from_secs(1)
+        ));
+
+        fs::remove_dir_all(&root).unwrap();
+    }
+
+    #[test]
+    fn test_timed_lines() {
+        let lines = [
+            "[23:59:58] [Server thread/INFO]: Saving chunks",
+            "java.lang.RuntimeException",
+            "[00:00:01] [Server thread/INFO]: Done",
+        ];
+
+        let timed = timed_lines(lines.map(str::to_owned).to_vec(), 19000);
+        let times = timed.iter().map(|line| line.time).collect::<Vec<_>>();
+
+        // The log started the day before it was last written to
+        let first_day = 18999 * DAY_SECS;
+        assert_eq!(
+            times,
+            [
+                first_day + 86398,
+                first_day + 86398,
+                first_day + DAY_SECS + 1
+            ]
+        );
+
+        assert_eq!(archived_log_day("2022-01-31-2.log.gz"), Some((19023, 2)));
+        assert_eq!(archived_log_day("latest.log"), None);
+    }
+}
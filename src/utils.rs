@@ -1,7 +1,34 @@
-use std::path::PathBuf;
+use std::collections::HashMap;
+use std::fs;
+use std::net::{Ipv4Addr, SocketAddr, TcpStream};
+use std::path::{Path, PathBuf};
+use std::process::{Command, Stdio};
+use std::sync::Mutex;
+use std::thread;
+use std::time::{Duration, Instant};
+
+use color_eyre::Result;
+
+use crate::arg_types::ServerSelector;
+
+/// Sync directories that sync into a server directory of the same name
+pub const NAMED_SYNC_DIRS: [&str; 2] = ["plugins", "mods"];
 
 type ServerInfo = (u8, u16, PathBuf, String);
 
+/// Matching servers, or every server if no selectors are given
+pub fn select_servers<'a>(
+    server_iter: impl Iterator<Item = ServerInfo> + 'a,
+    selectors: &'a [ServerSelector],
+) -> impl Iterator<Item = ServerInfo> + 'a {
+    server_iter.filter(move |(idx, port, directory, _)| {
+        selectors.is_empty()
+            || selectors
+                .iter()
+                .any(|selector| selector.matches(*idx, *port, directory))
+    })
+}
+
 pub fn server_iter(
     server_count: u8,
     start_port: u16,
@@ -18,3 +45,140 @@ pub fn server_iter(
         (idx, port, directory, motd)
     })
 }
+
+/// Formats seconds since the unix epoch as an RFC 3339 UTC timestamp
+pub fn format_unix_timestamp(secs: u64) -> String {
+    let days = (secs / 86400) as i64;
+    let time = secs % 86400;
+
+    // Civil from days, see http://howardhinnant.github.io/date_algorithms.html
+    let z = days + 719468;
+    let era = z.div_euclid(146097);
+    let doe = z.rem_euclid(146097);
+    let yoe = (doe - doe / 1460 + doe / 36524 - doe / 146096) / 365;
+    let doy = doe - (365 * yoe + yoe / 4 - yoe / 100);
+    let mp = (5 * doy + 2) / 153;
+    let day = doy - (153 * mp + 2) / 5 + 1;
+    let month = if mp < 10 { mp + 3 } else { mp - 9 };
+    let year = yoe + era * 400 + i64::from(month <= 2);
+
+    format!(
+        "{:04}-{:02}-{:02}T{:02}:{:02}:{:02}Z",
+        year,
+        month,
+        day,
+        time / 3600,
+        (time % 3600) / 60,
+        time % 60
+    )
+}
+
+/// Days since the unix epoch of a calendar date, the inverse of `format_unix_timestamp`
+pub fn days_from_civil(year: i64, month: i64, day: i64) -> i64 {
+    let year = if month <= 2 { year - 1 } else { year };
+    let era = year.div_euclid(400);
+    let yoe = year.rem_euclid(400);
+    let doy = (153 * ((month + 9) % 12) + 2) / 5 + day - 1;
+    let doe = yoe * 365 + yoe / 4 - yoe / 100 + doy;
+
+    era * 146097 + doe - 719468
+}
+
+pub fn sync_target_dir(source_dir: &Path, server_directory: &Path) -> PathBuf {
+    match source_dir.file_name() {
+        Some(dir) if NAMED_SYNC_DIRS.iter().any(|name| dir == *name) => server_directory.join(dir),
+        _ => server_directory.to_path_buf(),
+    }
+}
+
+/// Formats a duration as its two most significant units, such as `2h 05m`
+pub fn format_duration(secs: u64) -> String {
+    let (days, hours, mins) = (secs / 86400, secs / 3600 % 24, secs / 60 % 60);
+
+    if days > 0 {
+        format!("{}d {:02}h", days, hours)
+    } else if hours > 0 {
+        format!("{}h {:02}m", hours, mins)
+    } else if mins > 0 {
+        format!("{}m {:02}s", mins, secs % 60)
+    } else {
+        format!("{}s", secs)
+    }
+}
+
+/// Reads a server's `server.properties`, later duplicate keys override earlier ones
+pub fn read_server_properties(directory: &Path) -> Result<HashMap<String, String>> {
+    let path = directory.join("server.properties");
+    if !path.exists() {
+        return Ok(HashMap::new());
+    }
+
+    let properties = fs::read_to_string(path)?
+        .lines()
+        .map(str::trim)
+        .filter(|line| !line.is_empty() && !line.starts_with('#'))
+        .filter_map(|line| line.split_once('='))
+        .map(|(key, value)| (key.trim().to_owned(), value.trim().to_owned()))
+        .collect();
+
+    Ok(properties)
+}
+
+/// Trimmed stdout of a command, `None` if it fails
+pub fn command_output(command: &mut Command) -> Option<String> {
+    let output = command.stderr(Stdio::null()).output().ok()?;
+    if !output.status.success() {
+        return None;
+    }
+
+    let stdout = String::from_utf8(output.stdout).ok()?;
+    Some(stdout.trim().to_owned())
+}
+
+/// Polls until something accepts connections on the local port, returns `false` on timeout
+pub fn wait_for_port(port: u16, timeout: Duration) -> bool {
+    let addr = SocketAddr::from((Ipv4Addr::LOCALHOST, port));
+    let deadline = Instant::now() + timeout;
+
+    while Instant::now() < deadline {
+        if TcpStream::connect_timeout(&addr, Duration::from_secs(1)).is_ok() {
+            return true;
+        }
+
+        thread::sleep(Duration::from_secs(1));
+    }
+
+    false
+}
+
+/// Runs `f` on each item with at most `limit` running at once, returning results in order
+pub fn run_parallel<T, R, F>(items: Vec<T>, limit: usize, f: F) -> Vec<R>
+where
+    T: Send,
+    R: Send,
+    F: Fn(T) -> R + Sync,
+{
+    let threads = limit.clamp(1, items.len().max(1));
+    let queue = Mutex::new(items.into_iter().enumerate());
+    let results = Mutex::new(vec![]);
+
+    thread::scope(|scope| {
+        for _ in 0..threads {
+            scope.spawn(|| loop {
+                let next = queue.lock().unwrap().next();
+                match next {
+                    Some((idx, item)) => {
+                        let result = f(item);
+                        results.lock().unwrap().push((idx, result));
+                    }
+
+                    None => break,
+                }
+            });
+        }
+    });
+
+    let mut results = results.into_inner().unwrap();
+    results.sort_by_key(|(idx, _)| *idx);
+    results.into_iter().map(|(_, result)| result).collect()
+}